@@ -0,0 +1,234 @@
+use crate::field::{gradient_of, Bounds, ScalarField};
+
+/// Which boolean a [`Csg`] node computes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Boolean {
+    /// Inside either operand.
+    #[default]
+    Union,
+    /// Inside both operands.
+    Intersection,
+    /// Inside `a` but not `b`.
+    Difference,
+}
+
+/// How the two surfaces are joined where they meet. Smooth blends with a
+/// radius of zero or less join as [`Blend::Hard`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Blend {
+    /// Sharp crease.
+    #[default]
+    Hard,
+    /// Quadratic polynomial smooth minimum.
+    Polynomial { radius: f32 },
+    /// Exponential smooth minimum, blends over the whole field.
+    Exponential { radius: f32 },
+    /// 45 degree bevel of width `radius`.
+    Chamfer { radius: f32 },
+    /// Quarter circle fillet of radius `radius`.
+    Round { radius: f32 },
+}
+
+impl Blend {
+    /// Blended minimum of two distances, together with its partial
+    /// derivatives with respect to each of them.
+    fn min(&self, a: f32, b: f32) -> (f32, f32, f32) {
+        let hard = if a < b { (a, 1.0, 0.0) } else { (b, 0.0, 1.0) };
+        // Also keeps the divisions by the radius below from giving NaN
+        if self.reach() <= 0.0 {
+            return hard;
+        }
+
+        match *self {
+            Blend::Hard => hard,
+            Blend::Polynomial { radius } => {
+                let h = (0.5 + 0.5 * (b - a) / radius).clamp(0.0, 1.0);
+                let value = b + h * (a - b) - radius * h * (1.0 - h);
+                // The terms from dh cancel out, leaving plain weights
+                (value, h, 1.0 - h)
+            }
+            Blend::Exponential { radius } => {
+                let (low, difference) = (a.min(b), (a - b).abs());
+                let value = low - radius * (-difference / radius).exp().ln_1p();
+                let weight_a = 1.0 / (1.0 + ((a - b) / radius).exp());
+                (value, weight_a, 1.0 - weight_a)
+            }
+            Blend::Chamfer { radius } => {
+                let chamfer = (a + b - radius) * std::f32::consts::FRAC_1_SQRT_2;
+                if chamfer < hard.0 {
+                    let weight = std::f32::consts::FRAC_1_SQRT_2;
+                    (chamfer, weight, weight)
+                } else {
+                    hard
+                }
+            }
+            Blend::Round { radius } => {
+                let (ua, ub) = ((radius - a).max(0.0), (radius - b).max(0.0));
+                let length = (ua * ua + ub * ub).sqrt();
+                if length == 0.0 {
+                    return hard;
+                }
+
+                (radius.max(hard.0) - length, ua / length, ub / length)
+            }
+        }
+    }
+
+    /// How far the blend can grow the surface past either operand.
+    fn reach(&self) -> f32 {
+        match *self {
+            Blend::Hard => 0.0,
+            Blend::Polynomial { radius }
+            | Blend::Exponential { radius }
+            | Blend::Chamfer { radius }
+            // Also catches NaN radii
+            | Blend::Round { radius } => radius.max(0.0),
+        }
+    }
+}
+
+/// Constructive solid geometry node combining two fields.
+///
+/// The operands are treated as distances to their `0.0` isosurface, positive
+/// values being inside, so blend radii are in world units for signed distance
+/// fields. Wrap fields with a different isolevel, like metaballs, in
+/// [`Isolevel`] first. Nodes nest to build trees, boxing the operands when
+/// the tree is built at runtime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Csg<A, B> {
+    pub a: A,
+    pub b: B,
+    pub boolean: Boolean,
+    pub blend: Blend,
+}
+
+impl<A: ScalarField, B: ScalarField> Csg<A, B> {
+    pub fn new(a: A, b: B, boolean: Boolean, blend: Blend) -> Csg<A, B> {
+        Csg {
+            a,
+            b,
+            boolean,
+            blend,
+        }
+    }
+
+    pub fn union(a: A, b: B, blend: Blend) -> Csg<A, B> {
+        Csg::new(a, b, Boolean::Union, blend)
+    }
+
+    pub fn intersection(a: A, b: B, blend: Blend) -> Csg<A, B> {
+        Csg::new(a, b, Boolean::Intersection, blend)
+    }
+
+    pub fn difference(a: A, b: B, blend: Blend) -> Csg<A, B> {
+        Csg::new(a, b, Boolean::Difference, blend)
+    }
+
+    /// Combined value with the partial derivatives towards `a` and `b`.
+    ///
+    /// Every boolean is the blended union of the operands' distances, with
+    /// signs flipped for intersection (`max(a, b) = -min(-a, -b)`) and for
+    /// the subtracted operand.
+    fn combine(&self, a: f32, b: f32) -> (f32, f32, f32) {
+        // Field values are negated distances
+        let (da, db) = (-a, -b);
+
+        match self.boolean {
+            Boolean::Union => {
+                let (d, wa, wb) = self.blend.min(da, db);
+                (-d, wa, wb)
+            }
+            Boolean::Intersection => {
+                let (d, wa, wb) = self.blend.min(-da, -db);
+                (d, wa, wb)
+            }
+            Boolean::Difference => {
+                let (d, wa, wb) = self.blend.min(-da, db);
+                (d, wa, -wb)
+            }
+        }
+    }
+}
+
+impl<A: ScalarField, B: ScalarField> ScalarField for Csg<A, B> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.combine(self.a.value(x, y, z), self.b.value(x, y, z)).0
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let (_, wa, wb) = self.combine(self.a.value(x, y, z), self.b.value(x, y, z));
+        let ga = gradient_of(&self.a, x, y, z);
+        let gb = gradient_of(&self.b, x, y, z);
+
+        Some((
+            wa * ga.0 + wb * gb.0,
+            wa * ga.1 + wb * gb.1,
+            wa * ga.2 + wb * gb.2,
+        ))
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        match self.boolean {
+            Boolean::Union => {
+                let bounds = self.a.bounds()?.union(&self.b.bounds()?);
+                Some(bounds.expand(self.blend.reach()))
+            }
+            Boolean::Intersection => match (self.a.bounds(), self.b.bounds()) {
+                (Some(a), Some(b)) => Some(a.intersection(&b)),
+                (a, b) => a.or(b),
+            },
+            Boolean::Difference => self.a.bounds(),
+        }
+    }
+
+    /// Operand colors mixed by how much each shapes the surface at the point.
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let (ca, cb) = (self.a.color(x, y, z), self.b.color(x, y, z));
+        let (Some(ca), Some(cb)) = (ca, cb) else {
+            return ca.or(cb);
+        };
+
+        let (_, wa, wb) = self.combine(self.a.value(x, y, z), self.b.value(x, y, z));
+        let (wa, wb) = (wa.abs(), wb.abs());
+        let t = if wa + wb > 0.0 { wb / (wa + wb) } else { 0.5 };
+
+        Some((
+            ca.0 + (cb.0 - ca.0) * t,
+            ca.1 + (cb.1 - ca.1) * t,
+            ca.2 + (cb.2 - ca.2) * t,
+            ca.3 + (cb.3 - ca.3) * t,
+        ))
+    }
+}
+
+/// Moves a field's `level` isosurface to `0.0`, so fields meshed at
+/// different thresholds can be combined with [`Csg`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Isolevel<F> {
+    pub field: F,
+    pub level: f32,
+}
+
+impl<F: ScalarField> Isolevel<F> {
+    pub fn new(field: F, level: f32) -> Isolevel<F> {
+        Isolevel { field, level }
+    }
+}
+
+impl<F: ScalarField> ScalarField for Isolevel<F> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.field.value(x, y, z) - self.level
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        self.field.gradient(x, y, z)
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.field.bounds()
+    }
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        self.field.color(x, y, z)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::field::ScalarField;
+use crate::grid::Samples;
+use crate::qef::Qef;
+use crate::surface_nets::dual_mesh;
+use crate::GridData;
+
+/// Runs dual contouring over `samples` of `field`.
+///
+/// Meshed like surface nets, but each cell's vertex minimizes the distance
+/// to the tangent planes at its edge crossings, the Hermite data, so it lands
+/// on the corners and creases where the planes meet. Vertices are kept
+/// inside their cell.
+pub(crate) fn dual_contour<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    closed: bool,
+    singular_value_threshold: f32,
+) -> GridData {
+    dual_mesh(
+        samples,
+        field,
+        threshold,
+        closed,
+        |lattice, cell, crossings| {
+            let mut qef = Qef::default();
+            for crossing in crossings {
+                match crossing.cap {
+                    Some(normal) => qef.add(lattice.position(crossing.point), normal),
+                    None => {
+                        let (a, b) = crossing.edge;
+                        let position = lattice.refine(a, b, crossing.t);
+                        qef.add(position, lattice.normal(position));
+                    }
+                }
+            }
+
+            lattice.clamp_to_cell(cell, qef.solve(singular_value_threshold))
+        },
+    )
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use crate::field::ScalarField;
+use crate::grid::Samples;
+use crate::marching::{march_cells, Lattice, Point};
+use crate::qef::Qef;
+use crate::vector::{cross, dot, length, scale, Vec3};
+use crate::GridData;
+
+/// A feature with a normal closer than this cosine to its crease direction,
+/// so a third face meets the crease, is a corner.
+const CORNER_COS: f32 = 0.7;
+
+/// Runs Extended Marching Cubes over `samples` of `field`.
+///
+/// Cells are triangulated by the marching cubes tables, from crossings
+/// pinned down on the field itself. Where the normals of a sheet of
+/// triangles through a cell spread wider than `feature_angle`, its triangles
+/// are replaced by a fan around a vertex on the crease or corner, placed by
+/// a QEF on the crossings' tangent planes. Edges between the fans of
+/// neighbouring cells are then flipped to join their feature vertices into
+/// feature lines.
+pub(crate) fn march_extended<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    closed: bool,
+    feature_angle: f32,
+) -> GridData {
+    let mut lattice = Lattice::new(samples, field, threshold, closed);
+    let sharp = feature_angle.cos();
+    let mut features = Vec::new();
+
+    march_cells(&mut lattice, exact_vertex, |lattice, cell, triangles| {
+        // Padding cells lie outside the domain and only hold caps, whose
+        // creases with the surface are already on the domain faces. A feature
+        // vertex clamped in there would land on the caps' own vertices.
+        if lattice.is_padding_cell(cell) {
+            lattice.mesh.indices.extend_from_slice(triangles);
+            return;
+        }
+
+        for sheet in sheets(triangles) {
+            match feature_vertex(lattice, cell, &sheet, sharp) {
+                Some(feature) => {
+                    fan(&mut lattice.mesh, &sheet, feature);
+                    features.push(feature);
+                }
+                None => lattice.mesh.indices.extend_from_slice(&sheet),
+            }
+        }
+    });
+
+    let mut is_feature = vec![false; lattice.mesh.vertices.len() / 3];
+    for &feature in &features {
+        is_feature[feature as usize] = true;
+    }
+    flip_edges(&mut lattice.mesh, &is_feature);
+
+    lattice.mesh
+}
+
+/// Adds the vertex for an edge crossing, on the field rather than
+/// interpolated so its normal is that of the right face.
+fn exact_vertex<F: ScalarField + ?Sized>(lattice: &mut Lattice<F>, a: Point, b: Point) -> u32 {
+    match lattice.crossing(a, b) {
+        (t, None) => {
+            let position = lattice.refine(a, b, t);
+            lattice.push_vertex(position, None)
+        }
+        // Capped on the domain face
+        (_, Some(_)) => lattice.edge_vertex(a, b),
+    }
+}
+
+/// Splits a cell's triangles into the connected sheets of surface passing
+/// through it.
+fn sheets(triangles: &[u32]) -> Vec<Vec<u32>> {
+    let triangles: Vec<&[u32]> = triangles.chunks(3).collect();
+    let mut labels: Vec<usize> = (0..triangles.len()).collect();
+
+    // Triangles sharing a vertex belong to the same sheet
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..triangles.len() {
+            for j in i + 1..triangles.len() {
+                let shared = triangles[i].iter().any(|v| triangles[j].contains(v));
+                if shared && labels[i] != labels[j] {
+                    let label = labels[i].min(labels[j]);
+                    labels[i] = label;
+                    labels[j] = label;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // Each sheet is labelled by its first triangle
+    (0..triangles.len())
+        .filter(|&i| labels[i] == i)
+        .map(|sheet| {
+            triangles
+                .iter()
+                .zip(&labels)
+                .filter(|&(_, &label)| label == sheet)
+                .flat_map(|(triangle, _)| triangle.iter().copied())
+                .collect()
+        })
+        .collect()
+}
+
+/// Adds the feature vertex of a sheet of triangles through `cell`, if its
+/// normals spread wider than the cosine `sharp`.
+///
+/// Following Kobbelt et al., the two normals furthest apart span the crease
+/// and a normal well off the plane they span makes it a corner, which
+/// decides whether the QEF pins the vertex down in two or three directions.
+fn feature_vertex<F: ScalarField + ?Sized>(
+    lattice: &mut Lattice<F>,
+    cell: Point,
+    sheet: &[u32],
+    sharp: f32,
+) -> Option<u32> {
+    let mut vertices = sheet.to_vec();
+    vertices.sort_unstable();
+    vertices.dedup();
+
+    let attribute = |data: &[f32], vertex: u32| {
+        let i = vertex as usize * 3;
+        (data[i], data[i + 1], data[i + 2])
+    };
+    let planes: Vec<(Vec3, Vec3)> = vertices
+        .iter()
+        .map(|&vertex| {
+            (
+                attribute(&lattice.mesh.vertices, vertex),
+                attribute(&lattice.mesh.normals, vertex),
+            )
+        })
+        // Cancelled out gradients say nothing about the surface
+        .filter(|&(_, normal)| length(normal) > 0.5)
+        .collect();
+
+    // The normal cone, by its two most different normals
+    let mut widest = (1.0, (0.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+    for (i, &(_, a)) in planes.iter().enumerate() {
+        for &(_, b) in &planes[i + 1..] {
+            if dot(a, b) < widest.0 {
+                widest = (dot(a, b), a, b);
+            }
+        }
+    }
+    let (cos, a, b) = widest;
+    if cos >= sharp {
+        return None;
+    }
+
+    let crease = cross(a, b);
+    // Opposite normals, a sheet folded onto itself rather than a feature
+    if length(crease) <= f32::EPSILON {
+        return None;
+    }
+    let crease = scale(crease, 1.0 / length(crease));
+    let corner = planes
+        .iter()
+        .any(|&(_, normal)| dot(normal, crease).abs() > CORNER_COS);
+
+    let mut qef = Qef::default();
+    for &(position, normal) in &planes {
+        qef.add(position, normal);
+    }
+    let position = qef.solve_rank(if corner { 3 } else { 2 });
+
+    Some(lattice.push_vertex(lattice.clamp_to_cell(cell, position), None))
+}
+
+/// Replaces a sheet of triangles by a fan around its feature vertex.
+fn fan(mesh: &mut GridData, sheet: &[u32], feature: u32) {
+    let edges: Vec<(u32, u32)> = sheet
+        .chunks(3)
+        .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+        .collect();
+
+    // The sheet's boundary, in the direction of its triangles
+    for &(a, b) in &edges {
+        if !edges.contains(&(b, a)) {
+            mesh.indices.extend_from_slice(&[a, b, feature]);
+        }
+    }
+}
+
+/// Flips the edges between fans of neighbouring cells so their feature
+/// vertices get joined, turning the zigzag along a crease into a line.
+fn flip_edges(mesh: &mut GridData, is_feature: &[bool]) {
+    let triangle_count = mesh.indices.len() / 3;
+    let mut edges: HashMap<(u32, u32), usize> = HashMap::with_capacity(mesh.indices.len());
+    for t in 0..triangle_count {
+        for k in 0..3 {
+            let (a, b) = (mesh.indices[t * 3 + k], mesh.indices[t * 3 + (k + 1) % 3]);
+            edges.insert((a, b), t);
+        }
+    }
+
+    let mut flipped = vec![false; triangle_count];
+    for t in 0..triangle_count {
+        let triangle = &mesh.indices[t * 3..t * 3 + 3];
+        let Some(k) = (0..3).find(|&k| is_feature[triangle[k] as usize]) else {
+            continue;
+        };
+        let (feature, a, b) = (triangle[k], triangle[(k + 1) % 3], triangle[(k + 2) % 3]);
+        if flipped[t] || is_feature[a as usize] || is_feature[b as usize] {
+            continue;
+        }
+
+        // The triangle across the edge from `a` to `b`
+        let Some(&other) = edges.get(&(b, a)) else {
+            continue;
+        };
+        let opposite = mesh.indices[other * 3..other * 3 + 3]
+            .iter()
+            .copied()
+            .find(|&vertex| vertex != a && vertex != b)
+            .unwrap_or(feature);
+        if flipped[other]
+            || opposite == feature
+            || !is_feature[opposite as usize]
+            || edges.contains_key(&(feature, opposite))
+            || edges.contains_key(&(opposite, feature))
+        {
+            continue;
+        }
+
+        // The quad feature, a, opposite, b split along its other diagonal
+        mesh.indices[t * 3..t * 3 + 3].copy_from_slice(&[feature, a, opposite]);
+        mesh.indices[other * 3..other * 3 + 3].copy_from_slice(&[opposite, b, feature]);
+        edges.insert((feature, opposite), other);
+        edges.insert((opposite, feature), t);
+        flipped[t] = true;
+        flipped[other] = true;
+    }
+}
@@ -0,0 +1,138 @@
+use std::rc::Rc;
+
+/// Axis-aligned box that encloses everything a field cares about.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: (f32, f32, f32),
+    pub max: (f32, f32, f32),
+}
+
+impl Bounds {
+    pub fn new(min: (f32, f32, f32), max: (f32, f32, f32)) -> Bounds {
+        Bounds { min, max }
+    }
+
+    /// Overlap of `self` and `other`, possibly empty (`min > max`).
+    pub fn intersection(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: (
+                self.min.0.max(other.min.0),
+                self.min.1.max(other.min.1),
+                self.min.2.max(other.min.2),
+            ),
+            max: (
+                self.max.0.min(other.max.0),
+                self.max.1.min(other.max.1),
+                self.max.2.min(other.max.2),
+            ),
+        }
+    }
+
+    /// Grows the box by `margin` on every side.
+    pub fn expand(&self, margin: f32) -> Bounds {
+        Bounds {
+            min: (
+                self.min.0 - margin,
+                self.min.1 - margin,
+                self.min.2 - margin,
+            ),
+            max: (
+                self.max.0 + margin,
+                self.max.1 + margin,
+                self.max.2 + margin,
+            ),
+        }
+    }
+
+    /// Smallest box containing both `self` and `other`.
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: (
+                self.min.0.min(other.min.0),
+                self.min.1.min(other.min.1),
+                self.min.2.min(other.min.2),
+            ),
+            max: (
+                self.max.0.max(other.max.0),
+                self.max.1.max(other.max.1),
+                self.max.2.max(other.max.2),
+            ),
+        }
+    }
+}
+
+/// A scalar function over 3D space that can be polygonized.
+///
+/// The surface is extracted where `value` crosses the threshold, with
+/// values at or above the threshold counting as "inside".
+pub trait ScalarField {
+    /// Value of the field at a point.
+    fn value(&self, x: f32, y: f32, z: f32) -> f32;
+
+    /// Gradient of the field at a point, if it can be computed directly.
+    ///
+    /// Returning `None` makes the mesher fall back to finite differences.
+    fn gradient(&self, _x: f32, _y: f32, _z: f32) -> Option<(f32, f32, f32)> {
+        None
+    }
+
+    /// Region outside of which the field contributes nothing, if known.
+    fn bounds(&self) -> Option<Bounds> {
+        None
+    }
+
+    /// `rgba` color of the surface at a point, for fields that carry one.
+    fn color(&self, _x: f32, _y: f32, _z: f32) -> Option<(f32, f32, f32, f32)> {
+        None
+    }
+}
+
+/// Forwards `ScalarField` through pointer types, so fields can be borrowed,
+/// boxed into trees or shared.
+macro_rules! forward_scalar_field {
+    ($($pointer:ty),*) => {$(
+        impl<F: ScalarField + ?Sized> ScalarField for $pointer {
+            fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+                (**self).value(x, y, z)
+            }
+
+            fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+                (**self).gradient(x, y, z)
+            }
+
+            fn bounds(&self) -> Option<Bounds> {
+                (**self).bounds()
+            }
+
+            fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+                (**self).color(x, y, z)
+            }
+        }
+    )*};
+}
+
+forward_scalar_field!(&F, Box<F>, Rc<F>);
+
+/// Gradient from central differences, for fields without an analytic one.
+pub fn finite_difference<F: ScalarField + ?Sized>(
+    field: &F,
+    x: f32,
+    y: f32,
+    z: f32,
+) -> (f32, f32, f32) {
+    let delta = 0.001; // Step size for finite differences
+
+    let dx = field.value(x + delta, y, z) - field.value(x - delta, y, z);
+    let dy = field.value(x, y + delta, z) - field.value(x, y - delta, z);
+    let dz = field.value(x, y, z + delta) - field.value(x, y, z - delta);
+
+    let scale = 0.5 / delta;
+    (dx * scale, dy * scale, dz * scale)
+}
+
+/// The field's own gradient, or a finite difference estimate if it has none.
+pub fn gradient_of<F: ScalarField + ?Sized>(field: &F, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+    field
+        .gradient(x, y, z)
+        .unwrap_or_else(|| finite_difference(field, x, y, z))
+}
@@ -0,0 +1,235 @@
+//! Readers for volume files, producing [`Samples`] ready for
+//! [`polygonize_samples`](crate::polygonize_samples), and for MagicaVoxel
+//! models, which become a colored [`VoxelVolume`] field.
+//!
+//! Every reader parses from bytes, so they also work without a file system;
+//! the `open_*` functions are conveniences for native code.
+
+mod mrc;
+mod nrrd;
+mod raw;
+mod vox;
+mod vtk;
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::grid::{Grid, Samples};
+
+pub use mrc::{open_mrc, read_mrc};
+pub use nrrd::{open_nrrd, read_nrrd};
+pub use raw::{open_raw, read_raw, RawLayout};
+pub use vox::{open_vox, read_vox, Vox, VoxModel, VoxelVolume};
+pub use vtk::{open_vtk, read_vtk};
+
+/// Why a volume couldn't be read.
+#[derive(Debug)]
+pub enum VolumeError {
+    Io(io::Error),
+    /// The file is malformed.
+    Format(String),
+    /// The file is valid, but uses something this crate doesn't read.
+    Unsupported(String),
+    /// The data section holds fewer bytes than the header promises.
+    Truncated {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for VolumeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VolumeError::Io(error) => write!(f, "{error}"),
+            VolumeError::Format(message) => write!(f, "malformed volume: {message}"),
+            VolumeError::Unsupported(message) => write!(f, "unsupported volume: {message}"),
+            VolumeError::Truncated { expected, found } => {
+                write!(
+                    f,
+                    "volume data truncated: expected {expected} bytes, found {found}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for VolumeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VolumeError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VolumeError {
+    fn from(error: io::Error) -> VolumeError {
+        VolumeError::Io(error)
+    }
+}
+
+/// Storage type of a single voxel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    /// IEEE half precision.
+    F16,
+    F32,
+    F64,
+}
+
+impl DataType {
+    /// Size of one voxel in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            DataType::I8 | DataType::U8 => 1,
+            DataType::I16 | DataType::U16 | DataType::F16 => 2,
+            DataType::I32 | DataType::U32 | DataType::F32 => 4,
+            DataType::F64 => 8,
+        }
+    }
+}
+
+/// Byte order of multi-byte voxels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Reads a volume, picking the format from the file extension: `.nrrd` and
+/// `.nhdr`, `.mrc`, `.map` and `.ccp4`, or `.vtk`. Headerless raw files
+/// need [`open_raw`] and their layout.
+pub fn open(path: impl AsRef<Path>) -> Result<Samples, VolumeError> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "nrrd" | "nhdr" => open_nrrd(path),
+        "mrc" | "map" | "ccp4" => open_mrc(path),
+        "vtk" => open_vtk(path),
+        _ => Err(VolumeError::Unsupported(format!(
+            "unknown volume extension `{extension}`"
+        ))),
+    }
+}
+
+/// Converts `count` binary voxels at the start of `bytes` to `f32`.
+fn decode(
+    bytes: &[u8],
+    data_type: DataType,
+    endian: Endian,
+    count: usize,
+) -> Result<Vec<f32>, VolumeError> {
+    let size = data_type.size();
+    let expected = byte_count(count, data_type)?;
+    if bytes.len() < expected {
+        return Err(VolumeError::Truncated {
+            expected,
+            found: bytes.len(),
+        });
+    }
+
+    let values = bytes[..expected].chunks_exact(size).map(|chunk| {
+        let mut word = [0u8; 8];
+        word[..size].copy_from_slice(chunk);
+        if endian == Endian::Big {
+            word[..size].reverse();
+        }
+
+        match data_type {
+            DataType::I8 => word[0] as i8 as f32,
+            DataType::U8 => word[0] as f32,
+            DataType::I16 => i16::from_le_bytes([word[0], word[1]]) as f32,
+            DataType::U16 => u16::from_le_bytes([word[0], word[1]]) as f32,
+            DataType::I32 => i32::from_le_bytes([word[0], word[1], word[2], word[3]]) as f32,
+            DataType::U32 => u32::from_le_bytes([word[0], word[1], word[2], word[3]]) as f32,
+            DataType::F16 => half_to_f32(u16::from_le_bytes([word[0], word[1]])),
+            DataType::F32 => f32::from_le_bytes([word[0], word[1], word[2], word[3]]),
+            DataType::F64 => f64::from_le_bytes(word) as f32,
+        }
+    });
+
+    Ok(values.collect())
+}
+
+fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+
+    match exponent {
+        0 => sign * mantissa * (2.0f32).powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * (2.0f32).powi(exponent - 15),
+    }
+}
+
+/// Parses `count` whitespace separated voxels.
+fn parse_ascii(text: &[u8], count: usize) -> Result<Vec<f32>, VolumeError> {
+    let text = String::from_utf8_lossy(text);
+    let values = text
+        .split_ascii_whitespace()
+        .take(count)
+        .map(|token| {
+            token
+                .parse::<f32>()
+                .map_err(|_| VolumeError::Format(format!("`{token}` is not a number")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if values.len() < count {
+        return Err(VolumeError::Format(format!(
+            "expected {count} values, found {}",
+            values.len()
+        )));
+    }
+
+    Ok(values)
+}
+
+/// Wraps decoded voxels, x-fastest, into [`Samples`].
+fn samples(
+    origin: (f32, f32, f32),
+    points: (usize, usize, usize),
+    spacing: (f32, f32, f32),
+    values: Vec<f32>,
+) -> Result<Samples, VolumeError> {
+    if points.0 == 0 || points.1 == 0 || points.2 == 0 {
+        return Err(VolumeError::Format(format!(
+            "empty volume of {}x{}x{} voxels",
+            points.0, points.1, points.2
+        )));
+    }
+
+    Samples::try_new(Grid::from_points(origin, points, spacing), values)
+}
+
+/// Number of voxels, guarding against headers with absurd sizes.
+fn voxel_count(points: (usize, usize, usize)) -> Result<usize, VolumeError> {
+    points
+        .0
+        .checked_mul(points.1)
+        .and_then(|count| count.checked_mul(points.2))
+        .ok_or_else(|| VolumeError::Format("volume dimensions overflow".to_string()))
+}
+
+/// Size in bytes of `count` voxels, guarding against headers with absurd
+/// sizes.
+fn byte_count(count: usize, data_type: DataType) -> Result<usize, VolumeError> {
+    count
+        .checked_mul(data_type.size())
+        .ok_or_else(|| VolumeError::Format("volume size overflows".to_string()))
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use super::{decode, samples, voxel_count, DataType, Endian, VolumeError};
+use crate::grid::Samples;
+
+const HEADER_SIZE: usize = 1024;
+
+/// Reads an MRC or CCP4 density map.
+///
+/// Voxel sizes come from the cell dimensions, the origin from the MRC2000
+/// origin or else the start indices, and the column/row/section axis mapping
+/// is undone so the samples are always x-fastest.
+pub fn read_mrc(bytes: &[u8]) -> Result<Samples, VolumeError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(VolumeError::Truncated {
+            expected: HEADER_SIZE,
+            found: bytes.len(),
+        });
+    }
+
+    let endian = match bytes[212] {
+        0x44 => Endian::Little,
+        0x11 => Endian::Big,
+        // Old files leave the stamp empty, fall back on a sane mode
+        _ if i32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]) as u32 > 16 => {
+            Endian::Big
+        }
+        _ => Endian::Little,
+    };
+    let word = |index: usize| {
+        let bytes = [
+            bytes[4 * index],
+            bytes[4 * index + 1],
+            bytes[4 * index + 2],
+            bytes[4 * index + 3],
+        ];
+        match endian {
+            Endian::Little => i32::from_le_bytes(bytes),
+            Endian::Big => i32::from_be_bytes(bytes),
+        }
+    };
+    let float = |index: usize| f32::from_bits(word(index) as u32);
+
+    let data_type = match word(3) {
+        0 => DataType::I8,
+        1 => DataType::I16,
+        2 => DataType::F32,
+        6 => DataType::U16,
+        12 => DataType::F16,
+        mode => return Err(VolumeError::Unsupported(format!("MRC mode {mode}"))),
+    };
+
+    let count = |value: i32| {
+        usize::try_from(value)
+            .map_err(|_| VolumeError::Format(format!("negative MRC dimension {value}")))
+    };
+    let stored = [count(word(0))?, count(word(1))?, count(word(2))?];
+    let starts = [word(4), word(5), word(6)];
+
+    // Which axis the columns, rows and sections run along
+    let mut mapping = [word(16), word(17), word(18)].map(|axis| axis as usize);
+    let mut sorted = mapping;
+    sorted.sort_unstable();
+    if sorted != [1, 2, 3] {
+        mapping = [1, 2, 3];
+    }
+    let mapping = mapping.map(|axis| axis - 1);
+
+    let mut points = [0; 3];
+    let mut start = [0; 3];
+    for (stored_axis, &axis) in mapping.iter().enumerate() {
+        points[axis] = stored[stored_axis];
+        start[axis] = starts[stored_axis];
+    }
+
+    let voxel = |axis: usize| {
+        let (length, sampling) = (float(10 + axis), word(7 + axis));
+        if sampling > 0 && length > 0.0 {
+            length / sampling as f32
+        } else {
+            1.0
+        }
+    };
+    let spacing = [voxel(0), voxel(1), voxel(2)];
+
+    let origin = [float(49), float(50), float(51)];
+    let origin = if origin
+        .iter()
+        .any(|&origin| origin != 0.0 && origin.is_finite())
+    {
+        origin
+    } else {
+        [0, 1, 2].map(|axis| start[axis] as f32 * spacing[axis])
+    };
+
+    let extended = count(word(23))?;
+    let data = bytes
+        .get(HEADER_SIZE + extended..)
+        .ok_or(VolumeError::Truncated {
+            expected: HEADER_SIZE + extended,
+            found: bytes.len(),
+        })?;
+    let points = (points[0], points[1], points[2]);
+    let stored_values = decode(data, data_type, endian, voxel_count(points)?)?;
+
+    let values = if mapping == [0, 1, 2] {
+        stored_values
+    } else {
+        let mut values = vec![0.0; stored_values.len()];
+        let mut index = 0;
+        for section in 0..stored[2] {
+            for row in 0..stored[1] {
+                for column in 0..stored[0] {
+                    let mut position = [0; 3];
+                    position[mapping[0]] = column;
+                    position[mapping[1]] = row;
+                    position[mapping[2]] = section;
+                    values[position[0] + points.0 * (position[1] + points.1 * position[2])] =
+                        stored_values[index];
+                    index += 1;
+                }
+            }
+        }
+        values
+    };
+
+    samples(
+        (origin[0], origin[1], origin[2]),
+        points,
+        (spacing[0], spacing[1], spacing[2]),
+        values,
+    )
+}
+
+pub fn open_mrc(path: impl AsRef<Path>) -> Result<Samples, VolumeError> {
+    read_mrc(&std::fs::read(path)?)
+}
@@ -0,0 +1,309 @@
+use std::path::Path;
+
+use super::{byte_count, decode, parse_ascii, samples, voxel_count, DataType, Endian, VolumeError};
+use crate::grid::Samples;
+use crate::vector::Vec3;
+
+/// Header fields the reader understands; everything else is ignored.
+struct Header {
+    data_type: DataType,
+    points: (usize, usize, usize),
+    encoding: Encoding,
+    endian: Endian,
+    spacing: (f32, f32, f32),
+    origin: (f32, f32, f32),
+    byte_skip: i64,
+    data_file: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Encoding {
+    Raw,
+    Ascii,
+    Gzip,
+}
+
+/// Reads an attached-header NRRD file with raw, ascii or gzip encoding.
+///
+/// Only the length of the space directions is used, so the volume always
+/// comes out axis-aligned.
+pub fn read_nrrd(bytes: &[u8]) -> Result<Samples, VolumeError> {
+    let (header, offset) = parse_header(bytes)?;
+    if header.data_file.is_some() {
+        return Err(VolumeError::Unsupported(
+            "detached NRRD data, use open_nrrd".to_string(),
+        ));
+    }
+
+    read_data(&header, &bytes[offset..])
+}
+
+/// Reads an NRRD file, following `data file` to the voxels of a detached
+/// `.nhdr` header.
+pub fn open_nrrd(path: impl AsRef<Path>) -> Result<Samples, VolumeError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    let (header, offset) = parse_header(&bytes)?;
+
+    match &header.data_file {
+        Some(file) => {
+            let directory = path.parent().unwrap_or_else(|| Path::new(""));
+            read_data(&header, &std::fs::read(directory.join(file))?)
+        }
+        None => read_data(&header, &bytes[offset..]),
+    }
+}
+
+/// Parses the header, returning it with the offset of the attached data.
+fn parse_header(bytes: &[u8]) -> Result<(Header, usize), VolumeError> {
+    if !bytes.starts_with(b"NRRD000") {
+        return Err(VolumeError::Format("missing NRRD magic".to_string()));
+    }
+
+    let mut data_type = None;
+    let mut dimension = None;
+    let mut sizes = None;
+    let mut encoding = None;
+    let mut endian = Endian::Little;
+    let mut spacings = None;
+    let mut directions = None;
+    let mut origin = (0.0, 0.0, 0.0);
+    let mut byte_skip = 0;
+    let mut data_file = None;
+
+    let mut offset = 0;
+    let mut first = true;
+    // The header ends at a blank line, or for detached headers possibly at
+    // the end of the file
+    while offset < bytes.len() {
+        let length = bytes[offset..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .unwrap_or(bytes.len() - offset);
+        let line = String::from_utf8_lossy(&bytes[offset..offset + length]);
+        let line = line.trim_end_matches('\r');
+        offset = (offset + length + 1).min(bytes.len());
+
+        if first {
+            first = false;
+            continue;
+        }
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with('#') || line.contains(":=") {
+            continue;
+        }
+        let Some((field, value)) = line.split_once(": ") else {
+            return Err(VolumeError::Format(format!(
+                "bad NRRD header line `{line}`"
+            )));
+        };
+        let value = value.trim();
+
+        match field.to_ascii_lowercase().as_str() {
+            "type" => data_type = Some(parse_type(value)?),
+            "dimension" => dimension = Some(parse_number::<usize>(value)?),
+            "sizes" => sizes = Some(parse_numbers::<usize>(value)?),
+            "encoding" => {
+                encoding = Some(match value {
+                    "raw" => Encoding::Raw,
+                    "ascii" | "text" | "txt" => Encoding::Ascii,
+                    "gzip" | "gz" => Encoding::Gzip,
+                    _ => return Err(VolumeError::Unsupported(format!("NRRD encoding `{value}`"))),
+                })
+            }
+            "endian" => {
+                endian = match value {
+                    "little" => Endian::Little,
+                    "big" => Endian::Big,
+                    _ => return Err(VolumeError::Format(format!("bad endian `{value}`"))),
+                }
+            }
+            "spacings" => spacings = Some(parse_numbers::<f32>(value)?),
+            "space directions" => directions = Some(parse_vectors(value)?),
+            "space origin" => {
+                origin = match parse_vectors(value)?.as_slice() {
+                    [Some(vector)] => *vector,
+                    _ => return Err(VolumeError::Format("bad space origin".to_string())),
+                }
+            }
+            "byte skip" => byte_skip = parse_number::<i64>(value)?,
+            "data file" | "datafile" => data_file = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    if dimension != Some(3) {
+        return Err(VolumeError::Unsupported(
+            "only three-dimensional NRRD volumes".to_string(),
+        ));
+    }
+    let points = match sizes.as_deref() {
+        Some(&[x, y, z]) => (x, y, z),
+        _ => return Err(VolumeError::Format("missing or bad sizes".to_string())),
+    };
+
+    let spacing = match (spacings, directions) {
+        (Some(spacings), _) if spacings.len() == 3 => {
+            let axis = |spacing: f32| if spacing.is_finite() { spacing } else { 1.0 };
+            (axis(spacings[0]), axis(spacings[1]), axis(spacings[2]))
+        }
+        (_, Some(directions)) if directions.len() == 3 => {
+            let axis = |direction: Option<Vec3>| match direction {
+                Some((x, y, z)) => (x * x + y * y + z * z).sqrt(),
+                None => 1.0,
+            };
+            (
+                axis(directions[0]),
+                axis(directions[1]),
+                axis(directions[2]),
+            )
+        }
+        _ => (1.0, 1.0, 1.0),
+    };
+
+    let header = Header {
+        data_type: data_type.ok_or(VolumeError::Format("missing type".to_string()))?,
+        points,
+        encoding: encoding.ok_or(VolumeError::Format("missing encoding".to_string()))?,
+        endian,
+        spacing,
+        origin,
+        byte_skip,
+        data_file,
+    };
+
+    Ok((header, offset))
+}
+
+fn read_data(header: &Header, data: &[u8]) -> Result<Samples, VolumeError> {
+    let count = voxel_count(header.points)?;
+
+    let values = match header.encoding {
+        Encoding::Ascii => parse_ascii(data, count)?,
+        Encoding::Raw => {
+            let size = byte_count(count, header.data_type)?;
+            // -1 means the voxels are the last bytes of the file
+            let skip = match header.byte_skip {
+                -1 => data.len().checked_sub(size).ok_or(VolumeError::Truncated {
+                    expected: size,
+                    found: data.len(),
+                })?,
+                skip => skip_bytes(skip)?,
+            };
+            let data = data.get(skip..).unwrap_or_default();
+            decode(data, header.data_type, header.endian, count)?
+        }
+        Encoding::Gzip => {
+            let data = gunzip(data)?;
+            let skip = skip_bytes(header.byte_skip)?;
+            let data = data.get(skip..).unwrap_or_default();
+            decode(data, header.data_type, header.endian, count)?
+        }
+    };
+
+    samples(header.origin, header.points, header.spacing, values)
+}
+
+fn skip_bytes(skip: i64) -> Result<usize, VolumeError> {
+    usize::try_from(skip).map_err(|_| VolumeError::Format(format!("bad byte skip {skip}")))
+}
+
+fn parse_type(name: &str) -> Result<DataType, VolumeError> {
+    Ok(match name {
+        "signed char" | "int8" | "int8_t" => DataType::I8,
+        "uchar" | "unsigned char" | "uint8" | "uint8_t" => DataType::U8,
+        "short" | "short int" | "signed short" | "signed short int" | "int16" | "int16_t" => {
+            DataType::I16
+        }
+        "ushort" | "unsigned short" | "unsigned short int" | "uint16" | "uint16_t" => DataType::U16,
+        "int" | "signed int" | "int32" | "int32_t" => DataType::I32,
+        "uint" | "unsigned int" | "uint32" | "uint32_t" => DataType::U32,
+        "float" => DataType::F32,
+        "double" => DataType::F64,
+        _ => return Err(VolumeError::Unsupported(format!("NRRD type `{name}`"))),
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, VolumeError> {
+    text.parse()
+        .map_err(|_| VolumeError::Format(format!("`{text}` is not a number")))
+}
+
+fn parse_numbers<T: std::str::FromStr>(text: &str) -> Result<Vec<T>, VolumeError> {
+    text.split_ascii_whitespace().map(parse_number).collect()
+}
+
+/// Parses `(x,y,z)` vectors separated by whitespace, `none` standing in for
+/// non-spatial axes.
+fn parse_vectors(text: &str) -> Result<Vec<Option<Vec3>>, VolumeError> {
+    text.split_ascii_whitespace()
+        .map(|vector| {
+            if vector == "none" {
+                return Ok(None);
+            }
+
+            let inner = vector
+                .strip_prefix('(')
+                .and_then(|vector| vector.strip_suffix(')'))
+                .ok_or_else(|| VolumeError::Format(format!("bad vector `{vector}`")))?;
+            match inner
+                .split(',')
+                .map(|component| parse_number::<f32>(component.trim()))
+                .collect::<Result<Vec<_>, _>>()?
+                .as_slice()
+            {
+                &[x, y, z] => Ok(Some((x, y, z))),
+                _ => Err(VolumeError::Unsupported(format!(
+                    "non-3D vector `{vector}`"
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// Inflates a single gzip member.
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, VolumeError> {
+    const TEXT: u8 = 1;
+    const HEADER_CRC: u8 = 2;
+    const EXTRA: u8 = 4;
+    const NAME: u8 = 8;
+    const COMMENT: u8 = 16;
+
+    let malformed = || VolumeError::Format("malformed gzip stream".to_string());
+    if bytes.len() < 10 || bytes[0..3] != [0x1f, 0x8b, 8] {
+        return Err(malformed());
+    }
+
+    let flags = bytes[3] & !TEXT;
+    let mut offset = 10;
+    if flags & EXTRA != 0 {
+        let length = bytes.get(offset..offset + 2).ok_or_else(malformed)?;
+        offset += 2 + u16::from_le_bytes([length[0], length[1]]) as usize;
+    }
+    for field in [NAME, COMMENT] {
+        if flags & field != 0 {
+            let end = bytes
+                .get(offset..)
+                .and_then(|rest| rest.iter().position(|&byte| byte == 0))
+                .ok_or_else(malformed)?;
+            offset += end + 1;
+        }
+    }
+    if flags & HEADER_CRC != 0 {
+        offset += 2;
+    }
+
+    let stream = bytes.get(offset..).ok_or_else(malformed)?;
+    miniz_oxide::inflate::decompress_to_vec(stream)
+        .map_err(|error| VolumeError::Format(format!("gzip: {error}")))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_bytes: &[u8]) -> Result<Vec<u8>, VolumeError> {
+    Err(VolumeError::Unsupported(
+        "gzip encoded NRRD needs the `gzip` feature".to_string(),
+    ))
+}
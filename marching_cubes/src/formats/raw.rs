@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use super::{decode, samples, voxel_count, DataType, Endian, VolumeError};
+use crate::grid::Samples;
+
+/// Everything a headerless raw file doesn't say about itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RawLayout {
+    /// Voxels along each axis, stored x-fastest, then y, then z.
+    pub points: (usize, usize, usize),
+    pub data_type: DataType,
+    pub endian: Endian,
+    pub spacing: (f32, f32, f32),
+    pub origin: (f32, f32, f32),
+    /// Bytes to skip before the voxels, for files with a header this crate
+    /// doesn't parse.
+    pub header: usize,
+}
+
+impl RawLayout {
+    /// Little-endian unit-spaced voxels at the origin.
+    pub fn new(points: (usize, usize, usize), data_type: DataType) -> RawLayout {
+        RawLayout {
+            points,
+            data_type,
+            endian: Endian::Little,
+            spacing: (1.0, 1.0, 1.0),
+            origin: (0.0, 0.0, 0.0),
+            header: 0,
+        }
+    }
+}
+
+pub fn read_raw(bytes: &[u8], layout: &RawLayout) -> Result<Samples, VolumeError> {
+    let data = bytes.get(layout.header..).ok_or(VolumeError::Truncated {
+        expected: layout.header,
+        found: bytes.len(),
+    })?;
+    let count = voxel_count(layout.points)?;
+    let values = decode(data, layout.data_type, layout.endian, count)?;
+
+    samples(layout.origin, layout.points, layout.spacing, values)
+}
+
+pub fn open_raw(path: impl AsRef<Path>, layout: &RawLayout) -> Result<Samples, VolumeError> {
+    read_raw(&std::fs::read(path)?, layout)
+}
@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use super::VolumeError;
+use crate::field::{Bounds, ScalarField};
+use crate::grid::{Grid, Samples};
+
+/// Contents of a MagicaVoxel `.vox` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vox {
+    pub models: Vec<VoxModel>,
+    /// `rgba` colors indexed by the voxels' color index; index 0 is unused.
+    pub palette: [(u8, u8, u8, u8); 256],
+}
+
+/// One model of a `.vox` file. MagicaVoxel models are z-up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VoxModel {
+    pub size: (usize, usize, usize),
+    /// Filled voxels as `(x, y, z, color index)`.
+    pub voxels: Vec<(u8, u8, u8, u8)>,
+}
+
+/// Reads the models and palette of a `.vox` file; the scene graph, layers
+/// and materials are ignored.
+pub fn read_vox(bytes: &[u8]) -> Result<Vox, VolumeError> {
+    if bytes.len() < 8 || &bytes[0..4] != b"VOX " {
+        return Err(VolumeError::Format("missing VOX magic".to_string()));
+    }
+
+    let mut vox = Vox {
+        models: Vec::new(),
+        palette: default_palette(),
+    };
+    let mut size = None;
+    let mut offset = 8;
+
+    while offset < bytes.len() {
+        let header = bytes
+            .get(offset..offset + 12)
+            .ok_or_else(|| VolumeError::Format("truncated VOX chunk header".to_string()))?;
+        let id = &header[0..4];
+        let content_size = read_u32(header, 4) as usize;
+        let children_size = read_u32(header, 8) as usize;
+        offset += 12;
+
+        let content = offset
+            .checked_add(content_size)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or(VolumeError::Truncated {
+                expected: offset.saturating_add(content_size),
+                found: bytes.len(),
+            })?;
+        offset += content_size;
+
+        match id {
+            // Everything else is a child of MAIN, so walk into it
+            b"MAIN" => continue,
+            b"SIZE" => {
+                if content.len() < 12 {
+                    return Err(VolumeError::Format("short SIZE chunk".to_string()));
+                }
+                let model_size = (
+                    read_u32(content, 0) as usize,
+                    read_u32(content, 4) as usize,
+                    read_u32(content, 8) as usize,
+                );
+                // Voxel coordinates are bytes, so nothing bigger can be filled
+                if model_size.0 > 256 || model_size.1 > 256 || model_size.2 > 256 {
+                    return Err(VolumeError::Format(format!(
+                        "model of {}x{}x{} voxels",
+                        model_size.0, model_size.1, model_size.2
+                    )));
+                }
+                size = Some(model_size);
+            }
+            b"XYZI" => {
+                let size = size
+                    .take()
+                    .ok_or_else(|| VolumeError::Format("XYZI chunk without SIZE".to_string()))?;
+                let count = content
+                    .get(0..4)
+                    .map(|count| read_u32(count, 0) as usize)
+                    .ok_or_else(|| VolumeError::Format("short XYZI chunk".to_string()))?;
+                let length = count.saturating_mul(4).saturating_add(4);
+                let voxels = content
+                    .get(4..length)
+                    .ok_or(VolumeError::Truncated {
+                        expected: length,
+                        found: content.len(),
+                    })?
+                    .chunks_exact(4)
+                    .map(|voxel| (voxel[0], voxel[1], voxel[2], voxel[3]))
+                    .collect();
+
+                vox.models.push(VoxModel { size, voxels });
+            }
+            b"RGBA" => {
+                // Color index i is stored in entry i - 1
+                for (color, entry) in vox.palette[1..].iter_mut().zip(content.chunks_exact(4)) {
+                    *color = (entry[0], entry[1], entry[2], entry[3]);
+                }
+            }
+            _ => {}
+        }
+
+        offset = offset.saturating_add(children_size);
+    }
+
+    Ok(vox)
+}
+
+pub fn open_vox(path: impl AsRef<Path>) -> Result<Vox, VolumeError> {
+    read_vox(&std::fs::read(path)?)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+/// The palette MagicaVoxel uses when a file has no RGBA chunk: a 6-level
+/// color cube without black, then ramps of blue, green, red and gray.
+fn default_palette() -> [(u8, u8, u8, u8); 256] {
+    let mut palette = [(0, 0, 0, 0); 256];
+    let mut index = 1;
+
+    for r in (0..6).rev() {
+        for g in (0..6).rev() {
+            for b in (0..6).rev() {
+                if r + g + b > 0 {
+                    palette[index] = (r * 0x33, g * 0x33, b * 0x33, 0xff);
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    const RAMP: [u8; 10] = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+    for channel in [2, 1, 0, 3] {
+        for level in RAMP {
+            palette[index] = match channel {
+                0 => (level, 0, 0, 0xff),
+                1 => (0, level, 0, 0xff),
+                2 => (0, 0, level, 0xff),
+                _ => (level, level, level, 0xff),
+            };
+            index += 1;
+        }
+    }
+
+    palette
+}
+
+/// Occupancy volume of a voxel model: 1.0 in filled voxels and 0.0 in empty
+/// ones, so its 0.5 isosurface wraps the voxels. Colors from the palette are
+/// blurred along with the occupancy and come out per vertex.
+///
+/// Voxel `(x, y, z)` sits at world position `(x, y, z)`, and the volume is
+/// padded with empty voxels so the mesh always comes out closed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoxelVolume {
+    pub occupancy: Samples,
+    /// Color channels premultiplied by occupancy, so blurring them keeps
+    /// colors from bleeding in from empty space.
+    colors: [Samples; 4],
+}
+
+impl VoxelVolume {
+    /// Builds the volume of `model`, smoothed by a Gaussian blur of standard
+    /// deviation `blur` voxels; `0.0` keeps the hard voxel edges.
+    ///
+    /// The blur reaches at most as far as the model's longest side, which
+    /// keeps the padding bounded however wide it is. An infinite or NaN
+    /// `blur` is an error.
+    pub fn new(
+        model: &VoxModel,
+        palette: &[(u8, u8, u8, u8); 256],
+        blur: f32,
+    ) -> Result<VoxelVolume, VolumeError> {
+        if !blur.is_finite() {
+            return Err(VolumeError::Format(format!("blur of {blur} voxels")));
+        }
+
+        let longest = model.size.0.max(model.size.1).max(model.size.2).max(1);
+        let radius = if blur > 0.0 {
+            ((3.0 * blur).ceil() as usize).min(longest)
+        } else {
+            0
+        };
+        let pad = radius + 1;
+        let points = (
+            model.size.0 + 2 * pad,
+            model.size.1 + 2 * pad,
+            model.size.2 + 2 * pad,
+        );
+        let grid = Grid::from_points(
+            (-(pad as f32), -(pad as f32), -(pad as f32)),
+            points,
+            (1.0, 1.0, 1.0),
+        );
+
+        // Occupancy and premultiplied rgba, blurred together
+        let mut channels = vec![[0.0f32; 5]; grid.point_count()];
+        for &(x, y, z, color) in &model.voxels {
+            let (x, y, z) = (x as usize, y as usize, z as usize);
+            if x >= model.size.0 || y >= model.size.1 || z >= model.size.2 {
+                continue;
+            }
+
+            let (r, g, b, a) = palette[color as usize];
+            channels[(x + pad) + points.0 * ((y + pad) + points.1 * (z + pad))] = [
+                1.0,
+                r as f32 / 255.0,
+                g as f32 / 255.0,
+                b as f32 / 255.0,
+                a as f32 / 255.0,
+            ];
+        }
+
+        if radius > 0 {
+            let kernel: Vec<f32> = (0..=2 * radius)
+                .map(|i| {
+                    let x = i as f32 - radius as f32;
+                    (-x * x / (2.0 * blur * blur)).exp()
+                })
+                .collect();
+            let total: f32 = kernel.iter().sum();
+            let kernel: Vec<f32> = kernel.iter().map(|weight| weight / total).collect();
+
+            let strides = [1, points.0, points.0 * points.1];
+            let lengths = [points.0, points.1, points.2];
+            for axis in 0..3 {
+                channels = blur_axis(&channels, &kernel, strides[axis], lengths[axis]);
+            }
+        }
+
+        let channel =
+            |c: usize| Samples::new(grid, channels.iter().map(|channel| channel[c]).collect());
+        Ok(VoxelVolume {
+            occupancy: channel(0),
+            colors: [channel(1), channel(2), channel(3), channel(4)],
+        })
+    }
+
+    /// The lattice the volume is stored on, to mesh it at full resolution.
+    pub fn grid(&self) -> Grid {
+        self.occupancy.grid
+    }
+}
+
+/// One pass of a separable blur along the axis with the given stride.
+fn blur_axis(channels: &[[f32; 5]], kernel: &[f32], stride: usize, length: usize) -> Vec<[f32; 5]> {
+    let radius = kernel.len() / 2;
+    let mut blurred = vec![[0.0; 5]; channels.len()];
+
+    for (index, target) in blurred.iter_mut().enumerate() {
+        let position = (index / stride) % length;
+        for (k, &weight) in kernel.iter().enumerate() {
+            let Some(source) = (position + k)
+                .checked_sub(radius)
+                .filter(|&source| source < length)
+            else {
+                continue;
+            };
+            let sample = &channels[index - position * stride + source * stride];
+            for (value, channel) in target.iter_mut().zip(sample) {
+                *value += weight * channel;
+            }
+        }
+    }
+
+    blurred
+}
+
+impl ScalarField for VoxelVolume {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.occupancy.value(x, y, z)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        self.occupancy.gradient(x, y, z)
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.occupancy.bounds()
+    }
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let occupancy = self.occupancy.value(x, y, z);
+        if occupancy <= f32::EPSILON {
+            return None;
+        }
+
+        let [r, g, b, a] = self
+            .colors
+            .each_ref()
+            .map(|channel| channel.value(x, y, z) / occupancy);
+        Some((r, g, b, a))
+    }
+}
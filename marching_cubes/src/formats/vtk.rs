@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use super::{decode, parse_ascii, samples, voxel_count, DataType, Endian, VolumeError};
+use crate::grid::Samples;
+
+/// Reads the point scalars of a legacy VTK `STRUCTURED_POINTS` dataset, in
+/// ASCII or big-endian binary form.
+pub fn read_vtk(bytes: &[u8]) -> Result<Samples, VolumeError> {
+    let mut lines = Lines { bytes, offset: 0 };
+
+    let version = lines.next_line()?;
+    if !version.starts_with("# vtk DataFile") {
+        return Err(VolumeError::Format("missing VTK version line".to_string()));
+    }
+    // Title, which may be blank
+    lines.next_raw_line()?;
+    let binary = match lines.next_line()?.to_ascii_uppercase().as_str() {
+        "ASCII" => false,
+        "BINARY" => true,
+        format => return Err(VolumeError::Format(format!("bad VTK format `{format}`"))),
+    };
+
+    let mut points = None;
+    let mut origin = (0.0, 0.0, 0.0);
+    let mut spacing = (1.0, 1.0, 1.0);
+    let data_type = loop {
+        let line = lines.next_line()?;
+        let mut words = line.split_ascii_whitespace();
+        let keyword = words.next().unwrap_or_default().to_ascii_uppercase();
+        let arguments: Vec<&str> = words.collect();
+
+        match keyword.as_str() {
+            "DATASET" => {
+                if arguments.first().map(|kind| kind.to_ascii_uppercase())
+                    != Some("STRUCTURED_POINTS".to_string())
+                {
+                    return Err(VolumeError::Unsupported(format!("VTK dataset `{line}`")));
+                }
+            }
+            "DIMENSIONS" => {
+                let [x, y, z] = parse_triple::<usize>(&arguments)?;
+                points = Some((x, y, z));
+            }
+            "ORIGIN" => {
+                let [x, y, z] = parse_triple::<f32>(&arguments)?;
+                origin = (x, y, z);
+            }
+            "SPACING" | "ASPECT_RATIO" => {
+                let [x, y, z] = parse_triple::<f32>(&arguments)?;
+                spacing = (x, y, z);
+            }
+            "POINT_DATA" => {}
+            "SCALARS" => {
+                if arguments
+                    .get(2)
+                    .is_some_and(|&components| components != "1")
+                {
+                    return Err(VolumeError::Unsupported(
+                        "multi-component VTK scalars".to_string(),
+                    ));
+                }
+                let data_type = parse_type(arguments.get(1).copied().unwrap_or_default())?;
+                if lines.peek_line().is_some_and(|line| {
+                    line.trim_start()
+                        .to_ascii_uppercase()
+                        .starts_with("LOOKUP_TABLE")
+                }) {
+                    lines.next_line()?;
+                }
+                break data_type;
+            }
+            _ => {
+                return Err(VolumeError::Unsupported(format!(
+                    "VTK section `{keyword}` before the point scalars"
+                )))
+            }
+        }
+    };
+
+    let points = points.ok_or(VolumeError::Format("missing DIMENSIONS".to_string()))?;
+    let count = voxel_count(points)?;
+    let data = &bytes[lines.offset..];
+    let values = if binary {
+        decode(data, data_type, Endian::Big, count)?
+    } else {
+        parse_ascii(data, count)?
+    };
+
+    samples(origin, points, spacing, values)
+}
+
+pub fn open_vtk(path: impl AsRef<Path>) -> Result<Samples, VolumeError> {
+    read_vtk(&std::fs::read(path)?)
+}
+
+/// Line reader that keeps track of where the binary data starts.
+struct Lines<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Lines<'_> {
+    fn next_raw_line(&mut self) -> Result<String, VolumeError> {
+        let rest = &self.bytes[self.offset..];
+        if rest.is_empty() {
+            return Err(VolumeError::Format(
+                "unexpected end of VTK header".to_string(),
+            ));
+        }
+
+        let length = rest
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .unwrap_or(rest.len());
+        self.offset = (self.offset + length + 1).min(self.bytes.len());
+        Ok(String::from_utf8_lossy(&rest[..length]).trim().to_string())
+    }
+
+    /// Next non-blank line.
+    fn next_line(&mut self) -> Result<String, VolumeError> {
+        loop {
+            let line = self.next_raw_line()?;
+            if !line.is_empty() {
+                return Ok(line);
+            }
+        }
+    }
+
+    fn peek_line(&self) -> Option<String> {
+        Lines {
+            bytes: self.bytes,
+            offset: self.offset,
+        }
+        .next_line()
+        .ok()
+    }
+}
+
+fn parse_triple<T: std::str::FromStr + Copy + Default>(
+    arguments: &[&str],
+) -> Result<[T; 3], VolumeError> {
+    let mut triple = [T::default(); 3];
+    if arguments.len() < 3 {
+        return Err(VolumeError::Format(format!(
+            "expected three values, found `{}`",
+            arguments.join(" ")
+        )));
+    }
+    for (value, argument) in triple.iter_mut().zip(arguments) {
+        *value = argument
+            .parse()
+            .map_err(|_| VolumeError::Format(format!("`{argument}` is not a number")))?;
+    }
+
+    Ok(triple)
+}
+
+fn parse_type(name: &str) -> Result<DataType, VolumeError> {
+    Ok(match name {
+        "char" => DataType::I8,
+        "unsigned_char" => DataType::U8,
+        "short" => DataType::I16,
+        "unsigned_short" => DataType::U16,
+        "int" => DataType::I32,
+        "unsigned_int" => DataType::U32,
+        "float" => DataType::F32,
+        "double" => DataType::F64,
+        _ => return Err(VolumeError::Unsupported(format!("VTK type `{name}`"))),
+    })
+}
@@ -0,0 +1,321 @@
+use crate::field::{Bounds, ScalarField};
+use crate::formats::VolumeError;
+
+/// Sampling lattice: an axis-aligned box split into `nx * ny * nz` cells.
+///
+/// The lattice has `n + 1` points along each axis, so vertices come out
+/// directly in the world units of `bounds`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Grid {
+    pub bounds: Bounds,
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+}
+
+impl Grid {
+    /// # Panics
+    ///
+    /// Panics if any of the cell counts is zero.
+    pub fn new(bounds: Bounds, nx: usize, ny: usize, nz: usize) -> Grid {
+        assert!(
+            nx > 0 && ny > 0 && nz > 0,
+            "expected at least one cell along each axis"
+        );
+
+        Grid { bounds, nx, ny, nz }
+    }
+
+    /// `resolution` cells per axis over the unit cube.
+    pub fn unit_cube(resolution: usize) -> Grid {
+        Grid::new(
+            Bounds::new((0.0, 0.0, 0.0), (1.0, 1.0, 1.0)),
+            resolution,
+            resolution,
+            resolution,
+        )
+    }
+
+    /// Picks the cell counts so cells are at most `cell_size` along every axis.
+    pub fn with_cell_size(bounds: Bounds, cell_size: f32) -> Grid {
+        let cells = |min: f32, max: f32| (((max - min) / cell_size).ceil() as usize).max(1);
+        Grid::new(
+            bounds,
+            cells(bounds.min.0, bounds.max.0),
+            cells(bounds.min.1, bounds.max.1),
+            cells(bounds.min.2, bounds.max.2),
+        )
+    }
+
+    /// Lattice around everything `field` cares about, with `resolution`
+    /// cubic cells across the longest side of its bounds and one more on
+    /// every side, so a surface lying on the bounds isn't clipped.
+    ///
+    /// `None` if the field has no bounds or they are empty.
+    pub fn fit<F: ScalarField + ?Sized>(field: &F, resolution: usize) -> Option<Grid> {
+        let bounds = field.bounds()?;
+        let sides = (
+            bounds.max.0 - bounds.min.0,
+            bounds.max.1 - bounds.min.1,
+            bounds.max.2 - bounds.min.2,
+        );
+        let extent = sides.0.max(sides.1).max(sides.2);
+        let empty = sides.0 < 0.0 || sides.1 < 0.0 || sides.2 < 0.0;
+        if empty || !(extent.is_finite() && extent > 0.0) || resolution == 0 {
+            return None;
+        }
+
+        let cell_size = extent / resolution as f32;
+        Some(Grid::with_cell_size(bounds.expand(cell_size), cell_size))
+    }
+
+    /// Lattice of `points` samples along each axis, `spacing` apart and
+    /// starting at `origin`, the usual layout of voxel data.
+    ///
+    /// A single point along an axis leaves the lattice flat, without cells
+    /// along it: it can still be sampled, but there's nothing to mesh.
+    pub fn from_points(
+        origin: (f32, f32, f32),
+        points: (usize, usize, usize),
+        spacing: (f32, f32, f32),
+    ) -> Grid {
+        let cells = |points: usize| points.saturating_sub(1);
+        let (nx, ny, nz) = (cells(points.0), cells(points.1), cells(points.2));
+        Grid {
+            bounds: Bounds::new(
+                origin,
+                (
+                    origin.0 + nx as f32 * spacing.0,
+                    origin.1 + ny as f32 * spacing.1,
+                    origin.2 + nz as f32 * spacing.2,
+                ),
+            ),
+            nx,
+            ny,
+            nz,
+        }
+    }
+
+    /// Number of lattice points, `(nx + 1) * (ny + 1) * (nz + 1)`.
+    pub fn point_count(&self) -> usize {
+        (self.nx + 1) * (self.ny + 1) * (self.nz + 1)
+    }
+
+    /// Size of a single cell along each axis, zero along flat ones.
+    pub fn cell_size(&self) -> (f32, f32, f32) {
+        let size = |min: f32, max: f32, cells: usize| {
+            if cells == 0 {
+                0.0
+            } else {
+                (max - min) / cells as f32
+            }
+        };
+        (
+            size(self.bounds.min.0, self.bounds.max.0, self.nx),
+            size(self.bounds.min.1, self.bounds.max.1, self.ny),
+            size(self.bounds.min.2, self.bounds.max.2, self.nz),
+        )
+    }
+
+    /// Maps (possibly fractional) lattice coordinates to world space.
+    pub fn position(&self, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+        let (sx, sy, sz) = self.cell_size();
+        (
+            self.bounds.min.0 + x * sx,
+            self.bounds.min.1 + y * sy,
+            self.bounds.min.2 + z * sz,
+        )
+    }
+}
+
+/// Field values at every lattice point of a [`Grid`], in one flat buffer.
+///
+/// Points are stored x-fastest, then y, then z, so a z-slice of the lattice
+/// is a contiguous run of `(nx + 1) * (ny + 1)` values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Samples {
+    pub grid: Grid,
+    pub values: Vec<f32>,
+}
+
+impl Samples {
+    /// Wraps caller-supplied values laid out like [`Samples::values`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` doesn't hold exactly one value per lattice point.
+    pub fn new(grid: Grid, values: Vec<f32>) -> Samples {
+        assert_eq!(
+            values.len(),
+            grid.point_count(),
+            "expected one value per lattice point"
+        );
+
+        Samples { grid, values }
+    }
+
+    /// [`Samples::new`], returning an error instead of panicking when
+    /// `values` doesn't hold exactly one value per lattice point.
+    pub fn try_new(grid: Grid, values: Vec<f32>) -> Result<Samples, VolumeError> {
+        let expected = [grid.nx, grid.ny, grid.nz]
+            .iter()
+            .try_fold(1usize, |count, &cells| {
+                count.checked_mul(cells.checked_add(1)?)
+            })
+            .ok_or_else(|| VolumeError::Format("lattice dimensions overflow".to_string()))?;
+        if values.len() != expected {
+            return Err(VolumeError::Format(format!(
+                "expected {expected} values, found {}",
+                values.len()
+            )));
+        }
+
+        Ok(Samples { grid, values })
+    }
+
+    /// [`Samples::new`] for 8-bit voxel data, keeping the raw values.
+    pub fn from_u8(grid: Grid, values: &[u8]) -> Samples {
+        Samples::new(grid, values.iter().map(|&value| value as f32).collect())
+    }
+
+    /// [`Samples::new`] for 16-bit voxel data, keeping the raw values.
+    pub fn from_u16(grid: Grid, values: &[u16]) -> Samples {
+        Samples::new(grid, values.iter().map(|&value| value as f32).collect())
+    }
+
+    pub fn from_field<F: ScalarField + ?Sized>(field: &F, grid: &Grid) -> Samples {
+        let mut samples = Samples {
+            grid: *grid,
+            values: vec![0.0; grid.point_count()],
+        };
+
+        for z in 0..=grid.nz {
+            for y in 0..=grid.ny {
+                for x in 0..=grid.nx {
+                    let (px, py, pz) = grid.position(x as f32, y as f32, z as f32);
+                    let index = samples.index(x, y, z);
+                    samples.values[index] = field.value(px, py, pz);
+                }
+            }
+        }
+
+        samples
+    }
+
+    /// Offset of lattice point `(x, y, z)` in `values`.
+    #[inline]
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + (self.grid.nx + 1) * (y + (self.grid.ny + 1) * z)
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.values[self.index(x, y, z)]
+    }
+
+    /// Central difference at a lattice point, one-sided on the border, in
+    /// value per lattice step.
+    fn lattice_gradient(&self, x: usize, y: usize, z: usize) -> (f32, f32, f32) {
+        let difference =
+            |below: f32, above: f32, steps: usize| (above - below) / steps.max(1) as f32;
+        let (nx, ny, nz) = (self.grid.nx, self.grid.ny, self.grid.nz);
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(nx));
+        let (y0, y1) = (y.saturating_sub(1), (y + 1).min(ny));
+        let (z0, z1) = (z.saturating_sub(1), (z + 1).min(nz));
+
+        (
+            difference(self.get(x0, y, z), self.get(x1, y, z), x1 - x0),
+            difference(self.get(x, y0, z), self.get(x, y1, z), y1 - y0),
+            difference(self.get(x, y, z0), self.get(x, y, z1), z1 - z0),
+        )
+    }
+
+    /// Lattice cell containing a world position, clamped to the grid, and the
+    /// position's fractional offset within it.
+    fn locate(&self, x: f32, y: f32, z: f32) -> ([usize; 3], [f32; 3]) {
+        let (sx, sy, sz) = self.grid.cell_size();
+        let min = self.grid.bounds.min;
+        let locate_axis = |position: f32, min: f32, size: f32, cells: usize| {
+            if cells == 0 {
+                return (0, 0.0);
+            }
+            let t = ((position - min) / size).clamp(0.0, cells as f32);
+            let cell = (t.floor() as usize).min(cells - 1);
+            (cell, t - cell as f32)
+        };
+
+        let (cx, fx) = locate_axis(x, min.0, sx, self.grid.nx);
+        let (cy, fy) = locate_axis(y, min.1, sy, self.grid.ny);
+        let (cz, fz) = locate_axis(z, min.2, sz, self.grid.nz);
+        ([cx, cy, cz], [fx, fy, fz])
+    }
+
+    /// Trilinear blend of `corner` over the cell around a world position.
+    fn interpolate<T>(
+        &self,
+        x: f32,
+        y: f32,
+        z: f32,
+        corner: impl Fn(usize, usize, usize) -> T,
+        mut accumulate: impl FnMut(T, f32),
+    ) {
+        let ([cx, cy, cz], [fx, fy, fz]) = self.locate(x, y, z);
+        let upper = |cell: usize, cells: usize| (cell + 1).min(cells);
+
+        for (dz, wz) in [(cz, 1.0 - fz), (upper(cz, self.grid.nz), fz)] {
+            for (dy, wy) in [(cy, 1.0 - fy), (upper(cy, self.grid.ny), fy)] {
+                for (dx, wx) in [(cx, 1.0 - fx), (upper(cx, self.grid.nx), fx)] {
+                    accumulate(corner(dx, dy, dz), wx * wy * wz);
+                }
+            }
+        }
+    }
+}
+
+/// Trilinear interpolation of the samples, clamped to the edge of the grid,
+/// so dense voxel data can be resampled or combined with other fields.
+impl ScalarField for Samples {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        let mut value = 0.0;
+        self.interpolate(
+            x,
+            y,
+            z,
+            |cx, cy, cz| self.get(cx, cy, cz),
+            |corner, weight| {
+                value += corner * weight;
+            },
+        );
+        value
+    }
+
+    /// Interpolated central differences, which give smoother normals on voxel
+    /// data than differentiating the trilinear blend.
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let mut gradient = (0.0, 0.0, 0.0);
+        self.interpolate(
+            x,
+            y,
+            z,
+            |cx, cy, cz| self.lattice_gradient(cx, cy, cz),
+            |corner, weight| {
+                gradient.0 += corner.0 * weight;
+                gradient.1 += corner.1 * weight;
+                gradient.2 += corner.2 * weight;
+            },
+        );
+
+        // Flat along axes without cells
+        let per_unit = |gradient: f32, size: f32| if size > 0.0 { gradient / size } else { 0.0 };
+        let (sx, sy, sz) = self.grid.cell_size();
+        Some((
+            per_unit(gradient.0, sx),
+            per_unit(gradient.1, sy),
+            per_unit(gradient.2, sz),
+        ))
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        Some(self.grid.bounds)
+    }
+}
@@ -1,256 +1,175 @@
+pub mod csg;
+mod dual_contouring;
+mod emc;
+pub mod field;
+pub mod formats;
+pub mod grid;
 pub mod lookup_tables;
-
-use std::{collections::HashMap, vec};
-
-use lookup_tables::{EDGE_CONNECTIONS, EDGE_TABLE, TRI_TABLE};
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-pub struct Metaball {
-    x: f32,
-    y: f32,
-    z: f32,
-    radius: f32,
-    influence: f32,
+mod marching;
+mod mc33;
+pub mod metaball;
+pub mod modifiers;
+pub mod noise;
+mod qef;
+pub mod sdf;
+mod surface_nets;
+pub mod terrain;
+mod tetrahedra;
+mod vector;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use csg::{Blend, Boolean, Csg, Isolevel};
+pub use field::{finite_difference, gradient_of, Bounds, ScalarField};
+pub use grid::{Grid, Samples};
+pub use metaball::{Kernel, Metaball, Skeleton};
+pub use modifiers::{Bend, Displace, Mirror, Repeat, Transform, Twist};
+pub use noise::{Basis, Fractal, Noise};
+pub use sdf::Sdf;
+pub use terrain::{Heightmap, Interpolation, Terrain};
+
+/// Triangle mesh produced by the meshers.
+///
+/// `vertices` and `normals` are flat `xyz` triplets, `indices` index into them
+/// three per triangle. `colors` holds an `rgba` quadruplet per vertex when the
+/// field has colors, white for vertices it has none for, and is empty
+/// otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GridData {
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>,
+    pub normals: Vec<f32>,
+    pub colors: Vec<f32>,
 }
 
-#[wasm_bindgen]
-impl Metaball {
-    #[wasm_bindgen(constructor)]
-    pub fn new(x: f32, y: f32, z: f32, radius: f32, influence: f32) -> Metaball {
-        Metaball {
-            x,
-            y,
-            z,
-            radius,
-            influence,
-        }
+/// Meshes the metaball blend over the unit cube, giving nothing for a
+/// resolution of zero.
+pub fn marching_cubes(resolution: usize, metaballs: &[Metaball], threshold: f32) -> GridData {
+    if resolution == 0 {
+        return GridData::default();
     }
+    polygonize(metaballs, &Grid::unit_cube(resolution), threshold)
 }
 
-#[wasm_bindgen]
-pub struct GridData {
-    vertices: Vec<f32>,
-    indices: Vec<u32>,
-    normals: Vec<f32>,
+/// Surface extraction algorithm.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Mode {
+    /// Classic table-driven marching cubes.
+    #[default]
+    MarchingCubes,
+    /// Splits every cell into six tetrahedra. Needs no lookup tables and has
+    /// no ambiguous cases, at the price of more, thinner triangles.
+    MarchingTetrahedra,
+    /// Resolves the ambiguous face and interior cases of marching cubes the
+    /// way Marching Cubes 33 does, so the mesh matches the topology of the
+    /// trilinear interpolant, with tunnels where it has them.
+    MarchingCubes33,
+    /// Naive surface nets: one vertex per cell the surface passes through,
+    /// joined by quads. Far better shaped triangles than marching cubes, the
+    /// usual choice for voxel terrain.
+    SurfaceNets,
+    /// Dual contouring: surface nets with each vertex placed where the
+    /// tangent planes at its cell's edge crossings meet, which keeps the
+    /// sharp corners and creases of boxes and CSG shapes.
+    DualContouring {
+        /// Directions the tangent planes barely constrain, with a singular
+        /// value below this fraction of the largest, are left at the average
+        /// of the crossings. Lower keeps more features but lets noisy
+        /// normals throw vertices around; `0.1` is a good start.
+        singular_value_threshold: f32,
+    },
+    /// Extended Marching Cubes: marching cubes, except that cells the
+    /// surface bends sharply in get a vertex on the crease or corner, which
+    /// neighbouring cells' vertices are joined to. Keeps the features of
+    /// hard surfaces while leaving smooth parts as marching cubes has them.
+    ExtendedMarchingCubes {
+        /// Angle in radians between normals within a cell above which it
+        /// holds a feature; `0.5`, about 30 degrees, is a good start.
+        feature_angle: f32,
+    },
 }
 
-#[wasm_bindgen]
-impl GridData {
-    #[wasm_bindgen(getter)]
-    pub fn vertices(&self) -> Vec<f32> {
-        self.vertices.clone()
-    }
-
-    #[wasm_bindgen(getter)]
-    pub fn indices(&self) -> Vec<u32> {
-        self.indices.clone()
-    }
-
-    #[wasm_bindgen(getter)]
-    pub fn normals(&self) -> Vec<f32> {
-        self.normals.clone()
-    }
+/// Knobs for the meshing entry points.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeshOptions {
+    /// Treat everything outside the grid as outside the surface, so the mesh
+    /// is capped where it leaves the domain and always comes out watertight.
+    pub closed: bool,
+    pub mode: Mode,
 }
 
-fn scalar_field(x: f32, y: f32, z: f32, metaballs: &Box<[Metaball]>) -> f32 {
-    metaballs.iter().fold(0.0, |sum, ball| {
-        let dx = x - ball.x;
-        let dy = y - ball.y;
-        let dz = z - ball.z;
-        let distance_squared = dx * dx + dy * dy + dz * dz;
-
-        // Hermite cubic interpolation for blending
-        let influence = ball.influence / (distance_squared + ball.radius);
-        let normalized_distance = (distance_squared / ball.radius).sqrt().min(1.0); // Clamp to [0, 1]
-        let smooth_factor = 1.0
-            - normalized_distance
-                * normalized_distance
-                * normalized_distance
-                * (normalized_distance * (normalized_distance * 6.0 - 15.0) + 10.0);
-
-        sum + influence * smooth_factor
-    })
+/// Extracts the `threshold` isosurface of any scalar field sampled on `grid`.
+pub fn polygonize<F: ScalarField + ?Sized>(field: &F, grid: &Grid, threshold: f32) -> GridData {
+    polygonize_with(field, grid, threshold, &MeshOptions::default())
 }
 
-#[wasm_bindgen]
-pub fn marching_cubes(resolution: usize, metaballs: Box<[Metaball]>, threshold: f32) -> GridData {
-    let grid_size = 1.0 / resolution as f32;
+/// [`polygonize`] with explicit [`MeshOptions`].
+pub fn polygonize_with<F: ScalarField + ?Sized>(
+    field: &F,
+    grid: &Grid,
+    threshold: f32,
+    options: &MeshOptions,
+) -> GridData {
+    let samples = Samples::from_field(field, grid);
 
-    let mut vertices: Vec<f32> = Vec::with_capacity(resolution.pow(3) as usize * 3);
-    let mut indices: Vec<u32> = Vec::with_capacity(resolution.pow(3) as usize);
-    let mut normals: Vec<f32> = Vec::with_capacity(resolution.pow(3) as usize * 3);
-
-    let mut scalar_cache: Vec<Vec<Vec<f32>>> =
-        vec![
-            vec![vec![0.0; resolution as usize + 1]; resolution as usize + 1];
-            resolution as usize + 1
-        ];
+    mesh(&samples, field, threshold, options)
+}
 
-    // Cache for computed vertex indices
-    let mut edge_to_vertex: HashMap<(usize, usize, usize, usize), u32> = HashMap::new();
+/// Extracts the `threshold` isosurface straight from already sampled values,
+/// such as voxel data from a scanner or simulation.
+///
+/// The field is never re-evaluated; normals come from central differences of
+/// the samples.
+pub fn polygonize_samples(samples: &Samples, threshold: f32, options: &MeshOptions) -> GridData {
+    mesh(samples, samples, threshold, options)
+}
 
-    for x in 0..resolution {
-        for y in 0..resolution {
-            for z in 0..resolution {
-                scalar_cache[x as usize][y as usize][z as usize] = scalar_field(
-                    x as f32 * grid_size,
-                    y as f32 * grid_size,
-                    z as f32 * grid_size,
-                    &metaballs,
-                );
-            }
+fn mesh<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    options: &MeshOptions,
+) -> GridData {
+    match options.mode {
+        Mode::MarchingCubes => marching::march(samples, field, threshold, options.closed),
+        Mode::MarchingTetrahedra => {
+            tetrahedra::march_tetrahedra(samples, field, threshold, options.closed)
         }
-    }
-
-    let compute_gradient = |x: f32, y: f32, z: f32| -> (f32, f32, f32) {
-        let delta = 0.001; // Step size for finite differences
-
-        let dx =
-            scalar_field(x + delta, y, z, &metaballs) - scalar_field(x - delta, y, z, &metaballs);
-        let dy =
-            scalar_field(x, y + delta, z, &metaballs) - scalar_field(x, y - delta, z, &metaballs);
-        let dz =
-            scalar_field(x, y, z + delta, &metaballs) - scalar_field(x, y, z - delta, &metaballs);
-
-        let magnitude = (dx * dx + dy * dy + dz * dz).sqrt();
-        (-dx / magnitude, -dy / magnitude, -dz / magnitude) // Flip direction
-    };
-
-    let mut vertex_count = 0;
-
-    for x in 0..resolution {
-        for y in 0..resolution {
-            for z in 0..resolution {
-                // Define the 8 corners of the cube
-                let corners: [(usize, usize, usize); 8] = [
-                    (x, y, z),
-                    (x + 1, y, z),
-                    (x + 1, y + 1, z),
-                    (x, y + 1, z),
-                    (x, y, z + 1),
-                    (x + 1, y, z + 1),
-                    (x + 1, y + 1, z + 1),
-                    (x, y + 1, z + 1),
-                ];
-
-                // Compute scalar field values at corners
-                let mut corner_values = [0.0; 8];
-                for i in 0..8 {
-                    let (cx, cy, cz) = corners[i];
-                    corner_values[i] = scalar_cache[cx][cy][cz];
-                }
-
-                // Determine cube index using the scalar field values
-                let mut cube_index = 0;
-                for (i, &value) in corner_values.iter().enumerate() {
-                    if value >= threshold {
-                        cube_index |= 1 << i;
-                    }
-                }
-
-                // Skip if the cube is entirely inside or outside the surface
-                if EDGE_TABLE[cube_index] == 0 {
-                    continue;
-                }
-
-                // Interpolate vertices along edges
-                let mut edge_vertices = [None; 12];
-
-                for i in 0..12 {
-                    if (EDGE_TABLE[cube_index] & (1 << i)) != 0 {
-                        let (v1, v2) = EDGE_CONNECTIONS[i];
-                        let p1 = corners[v1];
-                        let p2 = corners[v2];
-                        let val1 = corner_values[v1];
-                        let val2 = corner_values[v2];
-
-                        let key = (
-                            x.min(p1.0).min(p2.0),
-                            y.min(p1.1).min(p2.1),
-                            z.min(p1.2).min(p2.2),
-                            i,
-                        );
-
-                        if let Some(&vertex_index) = edge_to_vertex.get(&key) {
-                            edge_vertices[i] = Some(vertex_index);
-                            continue;
-                        }
-
-                        let t = (threshold - val1) / (val2 - val1);
-                        let interpolated = (
-                            (p1.0 as f32 + t * (p2.0 as f32 - p1.0 as f32)) * grid_size,
-                            (p1.1 as f32 + t * (p2.1 as f32 - p1.1 as f32)) * grid_size,
-                            (p1.2 as f32 + t * (p2.2 as f32 - p1.2 as f32)) * grid_size,
-                        );
-
-                        vertices.extend_from_slice(&[
-                            interpolated.0,
-                            interpolated.1,
-                            interpolated.2,
-                        ]);
-                        let (nx, ny, nz) =
-                            compute_gradient(interpolated.0, interpolated.1, interpolated.2);
-                        normals.extend_from_slice(&[nx, ny, nz]);
-
-                        edge_to_vertex.insert(key, vertex_count);
-                        edge_vertices[i] = Some(vertex_count);
-                        vertex_count += 1;
-                    }
-                }
-
-                let start_index = cube_index * 16;
-                let tri_indices = &TRI_TABLE[start_index..start_index + 16];
-
-                for tri in tri_indices.chunks(3) {
-                    if tri[0] == -1 {
-                        break;
-                    }
-
-                    indices.extend_from_slice(&[
-                        edge_vertices[tri[2] as usize].unwrap(),
-                        edge_vertices[tri[1] as usize].unwrap(),
-                        edge_vertices[tri[0] as usize].unwrap(),
-                    ]);
-                }
-            }
+        Mode::MarchingCubes33 => mc33::march_33(samples, field, threshold, options.closed),
+        Mode::SurfaceNets => surface_nets::surface_nets(samples, field, threshold, options.closed),
+        Mode::DualContouring {
+            singular_value_threshold,
+        } => dual_contouring::dual_contour(
+            samples,
+            field,
+            threshold,
+            options.closed,
+            singular_value_threshold,
+        ),
+        Mode::ExtendedMarchingCubes { feature_angle } => {
+            emc::march_extended(samples, field, threshold, options.closed, feature_angle)
         }
     }
-
-    GridData {
-        vertices,
-        indices,
-        normals,
-    }
 }
 
-#[wasm_bindgen]
-pub fn visualize_sdf(resolution: usize, metaballs: Box<[Metaball]>, threshold: f32) -> GridData {
+/// Returns the lattice points whose field value is at or above `threshold`
+/// as a point cloud, useful for debugging a field.
+pub fn visualize_sdf<F: ScalarField + ?Sized>(field: &F, grid: &Grid, threshold: f32) -> GridData {
     // Create a grid of points and evaluate the scalar field at each point
-    let grid_size = 1.0 / resolution as f32;
-
     let mut vertices = Vec::new();
     let indices = Vec::new();
 
-    for x in 0..resolution {
-        for y in 0..resolution {
-            for z in 0..resolution {
-                let sdf = scalar_field(
-                    x as f32 * grid_size,
-                    y as f32 * grid_size,
-                    z as f32 * grid_size,
-                    &metaballs,
-                );
+    for x in 0..=grid.nx {
+        for y in 0..=grid.ny {
+            for z in 0..=grid.nz {
+                let (px, py, pz) = grid.position(x as f32, y as f32, z as f32);
+                let sdf = field.value(px, py, pz);
 
                 if sdf < threshold {
                     continue;
                 }
 
-                vertices.extend_from_slice(&[
-                    x as f32 * grid_size,
-                    y as f32 * grid_size,
-                    z as f32 * grid_size,
-                ]);
+                vertices.extend_from_slice(&[px, py, pz]);
             }
         }
     }
@@ -259,5 +178,6 @@ pub fn visualize_sdf(resolution: usize, metaballs: Box<[Metaball]>, threshold: f
         vertices,
         indices,
         normals: Vec::new(),
+        colors: Vec::new(),
     }
 }
@@ -0,0 +1,395 @@
+use crate::field::{gradient_of, ScalarField};
+use crate::grid::Samples;
+use crate::lookup_tables::{EDGE_CONNECTIONS, EDGE_TABLE, TRI_TABLE};
+use crate::GridData;
+
+/// Where the vertex for each of the 12 cube edges is cached: the edge axis
+/// and the offset of its lower lattice point within the cell.
+const EDGE_SLOTS: [(usize, Point); 12] = [
+    (0, (0, 0, 0)),
+    (1, (1, 0, 0)),
+    (0, (0, 1, 0)),
+    (1, (0, 0, 0)),
+    (0, (0, 0, 1)),
+    (1, (1, 0, 1)),
+    (0, (0, 1, 1)),
+    (1, (0, 0, 1)),
+    (2, (0, 0, 0)),
+    (2, (1, 0, 0)),
+    (2, (1, 1, 0)),
+    (2, (0, 1, 0)),
+];
+
+/// Lattice offsets of the 8 cube corners, in lookup table order.
+pub(crate) const CORNER_OFFSETS: [(usize, usize, usize); 8] = [
+    (0, 0, 0),
+    (1, 0, 0),
+    (1, 1, 0),
+    (0, 1, 0),
+    (0, 0, 1),
+    (1, 0, 1),
+    (1, 1, 1),
+    (0, 1, 1),
+];
+
+pub(crate) const NO_VERTEX: u32 = u32::MAX;
+
+/// Steps taken to pin down an edge crossing on the field, see
+/// [`Lattice::refine`].
+const REFINE_STEPS: usize = 8;
+
+/// A lattice point of the (possibly padded) lattice.
+pub(crate) type Point = (usize, usize, usize);
+
+/// What every mesher shares: the sampled lattice, padded by one layer of
+/// "outside" points in closed mode, and the mesh being built.
+///
+/// The field itself is only consulted for per-vertex attributes: normals
+/// point against its gradient, and colors are filled in when it has any.
+pub(crate) struct Lattice<'a, F: ?Sized> {
+    samples: &'a Samples,
+    field: &'a F,
+    pub threshold: f32,
+    pad: usize,
+    /// Cell counts of the padded lattice.
+    pub cells: Point,
+    pub mesh: GridData,
+}
+
+impl<'a, F: ScalarField + ?Sized> Lattice<'a, F> {
+    /// With `closed` set, the lattice is padded by one layer of "outside"
+    /// points so surfaces leaving the domain get capped on its faces.
+    pub fn new(samples: &'a Samples, field: &'a F, threshold: f32, closed: bool) -> Self {
+        let grid = &samples.grid;
+        let pad = closed as usize;
+        let cells = (grid.nx + 2 * pad, grid.ny + 2 * pad, grid.nz + 2 * pad);
+        let cell_count = cells.0 * cells.1 * cells.2;
+
+        Lattice {
+            samples,
+            field,
+            threshold,
+            pad,
+            cells,
+            mesh: GridData {
+                vertices: Vec::with_capacity(cell_count * 3),
+                indices: Vec::with_capacity(cell_count),
+                normals: Vec::with_capacity(cell_count * 3),
+                colors: Vec::new(),
+            },
+        }
+    }
+
+    /// Looks up a padded lattice point, `None` for the padding layer.
+    #[inline]
+    pub fn sample(&self, (x, y, z): Point) -> Option<f32> {
+        let grid = &self.samples.grid;
+        let (x, y, z) = (
+            x.checked_sub(self.pad)?,
+            y.checked_sub(self.pad)?,
+            z.checked_sub(self.pad)?,
+        );
+        (x <= grid.nx && y <= grid.ny && z <= grid.nz).then(|| self.samples.get(x, y, z))
+    }
+
+    /// Whether a lattice point is inside the surface, padding never is.
+    #[inline]
+    pub fn inside(&self, point: Point) -> bool {
+        self.sample(point)
+            .is_some_and(|value| value >= self.threshold)
+    }
+
+    /// Whether the cell whose lower corner is `cell` reaches into the padding
+    /// layer, where the surface gets capped on the domain faces.
+    pub fn is_padding_cell(&self, (x, y, z): Point) -> bool {
+        let (nx, ny, nz) = self.cells;
+        self.pad > 0 && (x == 0 || y == 0 || z == 0 || x + 1 == nx || y + 1 == ny || z + 1 == nz)
+    }
+
+    /// Maps fractional padded lattice coordinates to world space.
+    pub fn position(&self, (x, y, z): (f32, f32, f32)) -> (f32, f32, f32) {
+        let pad = self.pad as f32;
+        self.samples.grid.position(x - pad, y - pad, z - pad)
+    }
+
+    /// The field at a world position.
+    pub fn value(&self, (x, y, z): (f32, f32, f32)) -> f32 {
+        self.field.value(x, y, z)
+    }
+
+    /// Moves a world position into the cell whose lower corner is `cell`.
+    pub fn clamp_to_cell(&self, (x, y, z): Point, position: (f32, f32, f32)) -> (f32, f32, f32) {
+        let (x, y, z) = (x as f32, y as f32, z as f32);
+        let low = self.position((x, y, z));
+        let high = self.position((x + 1.0, y + 1.0, z + 1.0));
+        let clamp = |value: f32, low: f32, high: f32| value.clamp(low.min(high), low.max(high));
+
+        (
+            clamp(position.0, low.0, high.0),
+            clamp(position.1, low.1, high.1),
+            clamp(position.2, low.2, high.2),
+        )
+    }
+
+    /// Unit normal of the field at a world position, pointing against its
+    /// gradient.
+    pub fn normal(&self, (x, y, z): (f32, f32, f32)) -> (f32, f32, f32) {
+        let (dx, dy, dz) = gradient_of(self.field, x, y, z);
+
+        let magnitude = (dx * dx + dy * dy + dz * dz).sqrt();
+        // Opposing contributions, e.g. from negative metaballs, can cancel out
+        if magnitude <= f32::EPSILON {
+            return (0.0, 0.0, 0.0);
+        }
+
+        (-dx / magnitude, -dy / magnitude, -dz / magnitude) // Flip direction
+    }
+
+    /// Adds a vertex at a world position, with the normal given or else
+    /// taken from the field, and returns its index.
+    pub fn push_vertex(
+        &mut self,
+        position: (f32, f32, f32),
+        normal: Option<(f32, f32, f32)>,
+    ) -> u32 {
+        let index = (self.mesh.vertices.len() / 3) as u32;
+        let (nx, ny, nz) = normal.unwrap_or_else(|| self.normal(position));
+
+        self.mesh
+            .vertices
+            .extend_from_slice(&[position.0, position.1, position.2]);
+        self.mesh.normals.extend_from_slice(&[nx, ny, nz]);
+        let color = self.field.color(position.0, position.1, position.2);
+        // Once any vertex has a color they all need one, white where the
+        // field has none, e.g. around empty voxels
+        if color.is_some() || !self.mesh.colors.is_empty() {
+            self.mesh.colors.resize(index as usize * 4, 1.0);
+            let (r, g, b, a) = color.unwrap_or((1.0, 1.0, 1.0, 1.0));
+            self.mesh.colors.extend_from_slice(&[r, g, b, a]);
+        }
+
+        index
+    }
+
+    /// Where the surface crosses the lattice edge from `a` to `b`, exactly one
+    /// of which is inside, as a fraction of the way from `a`, and the normal
+    /// for edges into the padding.
+    ///
+    /// Such edges are capped right on the domain face, with the normal
+    /// pointing out of it.
+    pub fn crossing(&self, a: Point, b: Point) -> (f32, Option<(f32, f32, f32)>) {
+        let outwards = |from: Point, to: Point| {
+            let direction = (
+                to.0 as f32 - from.0 as f32,
+                to.1 as f32 - from.1 as f32,
+                to.2 as f32 - from.2 as f32,
+            );
+            let length =
+                (direction.0 * direction.0 + direction.1 * direction.1 + direction.2 * direction.2)
+                    .sqrt();
+            (
+                direction.0 / length,
+                direction.1 / length,
+                direction.2 / length,
+            )
+        };
+
+        match (self.sample(a), self.sample(b)) {
+            (Some(value_a), Some(value_b)) => {
+                ((self.threshold - value_a) / (value_b - value_a), None)
+            }
+            (Some(_), None) => (0.0, Some(outwards(a, b))),
+            (None, _) => (1.0, Some(outwards(b, a))),
+        }
+    }
+
+    /// Adds the vertex where the surface crosses the lattice edge from `a`
+    /// to `b` and returns its index.
+    pub fn edge_vertex(&mut self, a: Point, b: Point) -> u32 {
+        let (t, normal) = self.crossing(a, b);
+        let position = self.position((
+            a.0 as f32 + t * (b.0 as f32 - a.0 as f32),
+            a.1 as f32 + t * (b.1 as f32 - a.1 as f32),
+            a.2 as f32 + t * (b.2 as f32 - a.2 as f32),
+        ));
+
+        self.push_vertex(position, normal)
+    }
+
+    /// Where the field itself crosses the threshold along the lattice edge
+    /// from `a` to `b`, in world space, narrowed down from the fraction `t`
+    /// interpolated from the samples.
+    ///
+    /// Interpolating is only exact where the field is linear, which distance
+    /// fields aren't near corners, and a crossing that is off by a bit picks
+    /// up the normal of the wrong face.
+    pub fn refine(&self, a: Point, b: Point, t: f32) -> (f32, f32, f32) {
+        let a = self.position((a.0 as f32, a.1 as f32, a.2 as f32));
+        let b = self.position((b.0 as f32, b.1 as f32, b.2 as f32));
+        let at = |t: f32| {
+            (
+                a.0 + t * (b.0 - a.0),
+                a.1 + t * (b.1 - a.1),
+                a.2 + t * (b.2 - a.2),
+            )
+        };
+        let value = |t: f32| self.value(at(t)) - self.threshold;
+
+        let mut low = (0.0, value(0.0));
+        let mut high = (1.0, value(1.0));
+        if (low.1 >= 0.0) == (high.1 >= 0.0) {
+            return at(t);
+        }
+
+        // False position, keeping the crossing bracketed
+        let mut t = t;
+        for _ in 0..REFINE_STEPS {
+            let v = value(t);
+            if v == 0.0 {
+                break;
+            }
+            if (v >= 0.0) == (low.1 >= 0.0) {
+                low = (t, v);
+            } else {
+                high = (t, v);
+            }
+            t = low.0 - low.1 * (high.0 - low.0) / (high.1 - low.1);
+        }
+
+        at(t)
+    }
+}
+
+/// Vertex indices of edge crossings, keyed by an edge's lower lattice point
+/// and which of `directions` it steps in from there.
+///
+/// Edges of a layer of cells start on its lower or upper z-slice, so only
+/// those two slices are kept.
+pub(crate) struct EdgeCache {
+    directions: usize,
+    row: usize,
+    slices: [Vec<u32>; 2],
+}
+
+impl EdgeCache {
+    pub fn new((nx, ny, _): Point, directions: usize) -> EdgeCache {
+        let slice_len = (nx + 1) * (ny + 1) * directions;
+        EdgeCache {
+            directions,
+            row: nx + 1,
+            slices: [vec![NO_VERTEX; slice_len], vec![NO_VERTEX; slice_len]],
+        }
+    }
+
+    /// Call before each layer of cells, forgets the slice below the previous
+    /// layer.
+    pub fn start_layer(&mut self, z: usize) {
+        if z > 0 {
+            self.slices[(z + 1) % 2].fill(NO_VERTEX);
+        }
+    }
+
+    pub fn get_or_insert_with(
+        &mut self,
+        (x, y, z): Point,
+        direction: usize,
+        vertex: impl FnOnce() -> u32,
+    ) -> u32 {
+        let cached = &mut self.slices[z % 2][(x + y * self.row) * self.directions + direction];
+        if *cached == NO_VERTEX {
+            *cached = vertex();
+        }
+        *cached
+    }
+}
+
+/// Runs marching cubes over `samples` of `field`.
+pub(crate) fn march<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    closed: bool,
+) -> GridData {
+    let mut lattice = Lattice::new(samples, field, threshold, closed);
+    march_cells(
+        &mut lattice,
+        Lattice::edge_vertex,
+        |lattice, _, triangles| lattice.mesh.indices.extend_from_slice(triangles),
+    );
+
+    lattice.mesh
+}
+
+/// Walks the cells of `lattice` through the marching cubes tables.
+///
+/// `edge_vertex` adds the vertex for each crossed lattice edge, once per
+/// edge, and `emit` gets the lower corner of every cell the surface passes
+/// through with its triangles, counter-clockwise seen from outside.
+pub(crate) fn march_cells<'a, F: ScalarField + ?Sized>(
+    lattice: &mut Lattice<'a, F>,
+    mut edge_vertex: impl FnMut(&mut Lattice<'a, F>, Point, Point) -> u32,
+    mut emit: impl FnMut(&mut Lattice<'a, F>, Point, &[u32]),
+) {
+    let (nx, ny, nz) = lattice.cells;
+    let mut edges = EdgeCache::new(lattice.cells, 3);
+    let mut triangles = Vec::with_capacity(15);
+
+    for z in 0..nz {
+        edges.start_layer(z);
+
+        for y in 0..ny {
+            for x in 0..nx {
+                // Determine cube index from the corners, padding is always outside
+                let mut cube_index = 0;
+                for (i, &(dx, dy, dz)) in CORNER_OFFSETS.iter().enumerate() {
+                    if lattice.inside((x + dx, y + dy, z + dz)) {
+                        cube_index |= 1 << i;
+                    }
+                }
+
+                // Skip if the cube is entirely inside or outside the surface
+                if EDGE_TABLE[cube_index] == 0 {
+                    continue;
+                }
+
+                // Interpolate vertices along edges
+                let mut edge_vertices = [NO_VERTEX; 12];
+
+                for i in 0..12 {
+                    if (EDGE_TABLE[cube_index] & (1 << i)) == 0 {
+                        continue;
+                    }
+
+                    let (axis, (dx, dy, dz)) = EDGE_SLOTS[i];
+                    let (v1, v2) = EDGE_CONNECTIONS[i];
+                    let (p1, p2) = (CORNER_OFFSETS[v1], CORNER_OFFSETS[v2]);
+                    edge_vertices[i] =
+                        edges.get_or_insert_with((x + dx, y + dy, z + dz), axis, || {
+                            edge_vertex(
+                                lattice,
+                                (x + p1.0, y + p1.1, z + p1.2),
+                                (x + p2.0, y + p2.1, z + p2.2),
+                            )
+                        });
+                }
+
+                let start_index = cube_index * 16;
+                let tri_indices = &TRI_TABLE[start_index..start_index + 16];
+
+                triangles.clear();
+                for tri in tri_indices.chunks(3) {
+                    if tri[0] == -1 {
+                        break;
+                    }
+
+                    triangles.extend_from_slice(&[
+                        edge_vertices[tri[2] as usize],
+                        edge_vertices[tri[1] as usize],
+                        edge_vertices[tri[0] as usize],
+                    ]);
+                }
+                emit(lattice, (x, y, z), &triangles);
+            }
+        }
+    }
+}
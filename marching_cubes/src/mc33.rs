@@ -0,0 +1,628 @@
+use crate::field::ScalarField;
+use crate::grid::Samples;
+use crate::marching::{EdgeCache, Lattice, Point};
+use crate::vector::{add, cross, dot, scale, sub, Vec3};
+use crate::GridData;
+
+/// The faces of a cell as their corners in order around the face, with the
+/// outward normal. Corner `i` sits at offset `(i & 1, i >> 1 & 1, i >> 2)`.
+const FACES: [([usize; 4], Vec3); 6] = [
+    ([0, 2, 6, 4], (-1.0, 0.0, 0.0)),
+    ([1, 3, 7, 5], (1.0, 0.0, 0.0)),
+    ([0, 1, 5, 4], (0.0, -1.0, 0.0)),
+    ([2, 3, 7, 6], (0.0, 1.0, 0.0)),
+    ([0, 1, 3, 2], (0.0, 0.0, -1.0)),
+    ([4, 5, 7, 6], (0.0, 0.0, 1.0)),
+];
+
+fn offset(corner: usize) -> Point {
+    (corner & 1, corner >> 1 & 1, corner >> 2)
+}
+
+fn corner_position(corner: usize) -> Vec3 {
+    let (x, y, z) = offset(corner);
+    (x as f32, y as f32, z as f32)
+}
+
+/// Number of the cube edge between two adjacent corners: its axis times 4
+/// plus the other two coordinates of its corners.
+fn edge_id(a: usize, b: usize) -> usize {
+    let axis = (a ^ b).trailing_zeros() as usize;
+    let lower = a.min(b);
+    let rest = match axis {
+        0 => lower >> 1,
+        1 => (lower & 1) | (lower >> 2) << 1,
+        _ => lower & 3,
+    };
+    axis * 4 + rest
+}
+
+/// The lower and upper corner of a cube edge.
+fn edge_corners(edge: usize) -> (usize, usize) {
+    let (axis, rest) = (edge / 4, edge % 4);
+    let lower = match axis {
+        0 => rest << 1,
+        1 => (rest & 1) | (rest >> 1) << 2,
+        _ => rest,
+    };
+    (lower, lower | 1 << axis)
+}
+
+/// Runs a topologically correct marching cubes over `samples` of `field`, in
+/// the spirit of Chernyaev's Marching Cubes 33.
+///
+/// Rather than looking the triangulation up by corner signs alone, each cell
+/// decides its ambiguous faces with the asymptotic decider, which neighbours
+/// sharing the face always agree on, and follows the resulting contour
+/// segments around the cell into closed loops. A pair of loops is joined by
+/// a tunnel when the trilinear interpolant connects them through the cell
+/// interior. The mesh matches the topology of the trilinear interpolant and
+/// never has holes or non-manifold edges.
+pub(crate) fn march_33<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    closed: bool,
+) -> GridData {
+    let mut lattice = Lattice::new(samples, field, threshold, closed);
+    let (nx, ny, nz) = lattice.cells;
+    let mut edges = EdgeCache::new(lattice.cells, 3);
+
+    for z in 0..nz {
+        edges.start_layer(z);
+
+        for y in 0..ny {
+            for x in 0..nx {
+                let corner = |corner: usize| {
+                    let (dx, dy, dz) = offset(corner);
+                    (x + dx, y + dy, z + dz)
+                };
+                let values: [Option<f32>; 8] = std::array::from_fn(|i| lattice.sample(corner(i)));
+                let inside = values.map(|value| value.is_some_and(|value| value >= threshold));
+                if inside.iter().all(|&flag| flag == inside[0]) {
+                    continue;
+                }
+
+                let contour = Contour::new(&values, &inside, threshold);
+                let edge_loops = contour.loops();
+                let tunnels = contour.tunnels(&edge_loops, &values, threshold);
+                let loops: Vec<Vec<Crossing>> = edge_loops
+                    .into_iter()
+                    .map(|edge_loop| {
+                        edge_loop
+                            .into_iter()
+                            .map(|edge| {
+                                let (a, b) = edge_corners(edge);
+                                let (lower, upper) = (corner(a), corner(b));
+                                let vertex = edges.get_or_insert_with(lower, edge / 4, || {
+                                    lattice.edge_vertex(lower, upper)
+                                });
+                                (edge, vertex)
+                            })
+                            .collect()
+                    })
+                    .collect();
+
+                let mut tubed = vec![false; loops.len()];
+                for (a, b) in tunnels.into_iter().flatten() {
+                    if tubed[a] || tubed[b] {
+                        continue;
+                    }
+                    tubed[a] = true;
+                    tubed[b] = true;
+                    let center = lattice.position((x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5));
+                    stitch(&mut lattice, center, &loops[a], &loops[b]);
+                }
+                for (boundary, tubed) in loops.iter().zip(tubed) {
+                    if !tubed {
+                        fill(&mut lattice, boundary);
+                    }
+                }
+            }
+        }
+    }
+
+    lattice.mesh
+}
+
+/// How the surface crosses the faces of one cell.
+struct Contour {
+    /// For each crossed cube edge, the edge the contour continues to, going
+    /// counter-clockwise around the surface seen from outside.
+    next: [Option<usize>; 12],
+    /// Corners connected along the cell's faces without crossing the
+    /// surface, as the representative of each corner's group.
+    groups: [usize; 8],
+    inside: [bool; 8],
+}
+
+impl Contour {
+    fn new(values: &[Option<f32>; 8], inside: &[bool; 8], threshold: f32) -> Contour {
+        let mut contour = Contour {
+            next: [None; 12],
+            groups: std::array::from_fn(|corner| corner),
+            inside: *inside,
+        };
+
+        for edge in 0..12 {
+            let (a, b) = edge_corners(edge);
+            if inside[a] == inside[b] {
+                contour.join(a, b);
+            }
+        }
+
+        for (corners, normal) in FACES {
+            let crossed: Vec<usize> = (0..4)
+                .filter(|&k| inside[corners[k]] != inside[corners[(k + 1) % 4]])
+                .collect();
+            // Face edge k runs from corner k to corner k + 1
+            let face_edge = |k: usize| edge_id(corners[k % 4], corners[(k + 1) % 4]);
+
+            match crossed[..] {
+                [k, l] => contour.segment(face_edge(k), face_edge(l), normal),
+                [_, _, _, _] => {
+                    // Ambiguous face: the bilinear interpolant's saddle says
+                    // which diagonal pair is connected across it. Padding is
+                    // never connected.
+                    let relative =
+                        corners.map(|corner| values[corner].map(|v| v as f64 - threshold as f64));
+                    let inside_joined = match relative {
+                        [Some(a), Some(b), Some(c), Some(d)] => saddle_inside(a, b, c, d),
+                        _ => false,
+                    };
+
+                    // Join the connected diagonal and cut off the corners of
+                    // the other one
+                    for k in 0..4 {
+                        if inside[corners[k]] == inside_joined {
+                            contour.join(corners[k], corners[(k + 2) % 4]);
+                        } else {
+                            contour.segment(face_edge(k + 3), face_edge(k), normal);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        contour
+    }
+
+    fn group(&self, mut corner: usize) -> usize {
+        while self.groups[corner] != corner {
+            corner = self.groups[corner];
+        }
+        corner
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.group(a), self.group(b));
+        self.groups[a.max(b)] = a.min(b);
+    }
+
+    /// Adds the contour segment between two crossed edges of the face with
+    /// the given outward normal.
+    ///
+    /// The segment runs so the inside is on its right seen from outside the
+    /// cell; the orientation only depends on which corners are inside, so it
+    /// is worked out with edge midpoints.
+    fn segment(&mut self, a: usize, b: usize, normal: Vec3) {
+        let midpoint = |edge: usize| {
+            let (lower, upper) = edge_corners(edge);
+            scale(add(corner_position(lower), corner_position(upper)), 0.5)
+        };
+        let (lower, upper) = edge_corners(a);
+        let outwards = if self.inside[lower] {
+            sub(corner_position(upper), corner_position(lower))
+        } else {
+            sub(corner_position(lower), corner_position(upper))
+        };
+
+        if dot(cross(outwards, normal), sub(midpoint(b), midpoint(a))) > 0.0 {
+            self.next[a] = Some(b);
+        } else {
+            self.next[b] = Some(a);
+        }
+    }
+
+    /// The closed loops the segments form, as the cube edges they cross.
+    fn loops(&self) -> Vec<Vec<usize>> {
+        let mut visited = [false; 12];
+        let mut loops = Vec::new();
+
+        for start in 0..12 {
+            if visited[start] || self.next[start].is_none() {
+                continue;
+            }
+
+            let mut edge_loop = Vec::new();
+            let mut edge = start;
+            while !visited[edge] {
+                visited[edge] = true;
+                edge_loop.push(edge);
+                match self.next[edge] {
+                    Some(next) => edge = next,
+                    None => break,
+                }
+            }
+            loops.push(edge_loop);
+        }
+
+        loops
+    }
+
+    /// Pairs of `loops` joined by a tunnel through the cell, as their
+    /// indices.
+    ///
+    /// A tunnel runs where the trilinear interpolant has a saddle inside the
+    /// cell on one side of the threshold that joins two regions on that
+    /// side. The groups of corners those regions reach are then connected
+    /// through the interior, and the loops cutting each of them off from the
+    /// same group on the other side are the ends of the tube. There is one
+    /// candidate per saddle, whatever other loops the cell has.
+    fn tunnels(
+        &self,
+        loops: &[Vec<usize>],
+        values: &[Option<f32>; 8],
+        threshold: f32,
+    ) -> [Option<(usize, usize)>; 2] {
+        let Some(values) = values.iter().copied().collect::<Option<Vec<f32>>>() else {
+            return [None; 2];
+        };
+
+        body_saddles(&values).map(|saddle| {
+            let (position, value, joins_above) = saddle?;
+            if (value >= threshold) != joins_above {
+                return None;
+            }
+            let joined = self.joined_groups(&values, position, joins_above)?;
+
+            // The groups on either side of a loop, the tunnel's side first
+            let sides = |edge_loop: &Vec<usize>| {
+                let (a, b) = edge_corners(edge_loop[0]);
+                let (near, far) = if self.inside[a] == joins_above {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                (self.group(near), self.group(far))
+            };
+            (0..loops.len())
+                .flat_map(|i| (i + 1..loops.len()).map(move |j| (i, j)))
+                .find(|&(i, j)| {
+                    let ((near_i, far_i), (near_j, far_j)) = (sides(&loops[i]), sides(&loops[j]));
+                    far_i == far_j && (joined == [near_i, near_j] || joined == [near_j, near_i])
+                })
+        })
+    }
+
+    /// The two groups of corners joined by a saddle of the trilinear
+    /// interpolant at `position`, joining regions above its value or below.
+    ///
+    /// The cross-section of the cell through the saddle parallel to the xy
+    /// plane is bilinear, with a saddle of its own there. Its two opposite
+    /// quadrants beyond the saddle's value lie in the two regions the saddle
+    /// joins, and each reaches an edge of the cell along z, whose end
+    /// further beyond the value is a corner of that region.
+    fn joined_groups(&self, values: &[f32], position: Vec3, above: bool) -> Option<[usize; 2]> {
+        let section =
+            |corner: usize| values[corner] + position.2 * (values[corner | 4] - values[corner]);
+        // The sign of the section's xy term picks the diagonal beyond the
+        // saddle
+        let twist = section(0) + section(3) - section(1) - section(2);
+        let diagonal = if (twist > 0.0) == above {
+            [0, 3]
+        } else {
+            [1, 2]
+        };
+
+        let corners = diagonal.map(|corner| {
+            if (values[corner | 4] > values[corner]) == above {
+                corner | 4
+            } else {
+                corner
+            }
+        });
+        let groups = corners.map(|corner| self.group(corner));
+        let valid = corners.iter().all(|&corner| self.inside[corner] == above);
+        (valid && groups[0] != groups[1]).then_some(groups)
+    }
+}
+
+/// Whether the saddle of the bilinear interpolant over an ambiguous face,
+/// with corner values `a` to `d` in order around it relative to the
+/// threshold, lies inside.
+///
+/// The saddle's value is `(ac - bd) / (a + c - b - d)`. Diagonal corners
+/// share a side, so the denominator has the sign of `a` and never vanishes,
+/// and only the sign of the numerator needs checking. Products of `f32`s
+/// can't overflow as `f64`, leaving infinite samples, whose saddle counts
+/// as inside.
+fn saddle_inside(a: f64, b: f64, c: f64, d: f64) -> bool {
+    let numerator = a * c - b * d;
+    if numerator.is_nan() {
+        true
+    } else if a >= 0.0 {
+        numerator >= 0.0
+    } else {
+        numerator <= 0.0
+    }
+}
+
+/// Saddle points of the trilinear interpolant of the corner values strictly
+/// inside the cell, with the interpolant's value there and whether each one
+/// joins regions above its value rather than below.
+///
+/// Writing the interpolant as `k + a x + b y + c z + d xy + e yz + f zx +
+/// g xyz`, shifting the origin to `(-e/g, -f/g, -d/g)` leaves `g uvw` plus
+/// linear terms, whose critical points are found in closed form. The
+/// interpolant is harmonic, so the sign of the Hessian's determinant, `2 g^3
+/// uvw`, tells whether two of its curvatures are negative, making the saddle
+/// a pass between higher regions.
+fn body_saddles(values: &[f32]) -> [Option<(Vec3, f32, bool)>; 2] {
+    let v = |x: usize, y: usize, z: usize| values[x | y << 1 | z << 2];
+    let k = v(0, 0, 0);
+    let a = v(1, 0, 0) - k;
+    let b = v(0, 1, 0) - k;
+    let c = v(0, 0, 1) - k;
+    let d = v(1, 1, 0) - v(1, 0, 0) - v(0, 1, 0) + k;
+    let e = v(0, 1, 1) - v(0, 1, 0) - v(0, 0, 1) + k;
+    let f = v(1, 0, 1) - v(1, 0, 0) - v(0, 0, 1) + k;
+    let g =
+        v(1, 1, 1) - v(1, 1, 0) - v(1, 0, 1) - v(0, 1, 1) + v(1, 0, 0) + v(0, 1, 0) + v(0, 0, 1)
+            - k;
+    if g.abs() <= f32::EPSILON {
+        return [None; 2];
+    }
+
+    // The gradient vanishes where g vw = -alpha, g uw = -beta, g uv = -gamma
+    let alpha = a - d * f / g;
+    let beta = b - d * e / g;
+    let gamma = c - e * f / g;
+    let square = -alpha * beta * gamma / (g * g * g);
+    if alpha == 0.0 || beta == 0.0 || gamma == 0.0 || square < 0.0 {
+        return [None; 2];
+    }
+
+    let interpolant = |x: f32, y: f32, z: f32| {
+        k + a * x + b * y + c * z + d * x * y + e * y * z + f * z * x + g * x * y * z
+    };
+    [1.0, -1.0].map(|sign: f32| {
+        let uvw = sign * square.sqrt();
+        let x = -g * uvw / alpha - e / g;
+        let y = -g * uvw / beta - f / g;
+        let z = -g * uvw / gamma - d / g;
+        let within = |t: f32| t > 0.0 && t < 1.0;
+        (within(x) && within(y) && within(z))
+            .then(|| ((x, y, z), interpolant(x, y, z), g * uvw > 0.0))
+    })
+}
+
+/// A vertex of a contour loop: the cube edge it lies on and its index.
+type Crossing = (usize, u32);
+
+/// Whether two cube edges lie on a common face of the cell.
+///
+/// A triangle edge between such crossings would run along the face, where
+/// the neighbouring cell could put one too, so triangulations avoid them.
+fn share_face(a: usize, b: usize) -> bool {
+    // The faces of an edge are the two planes through it across the other
+    // axes, numbered by axis and side
+    let faces = |edge: usize| {
+        let (lower, _) = edge_corners(edge);
+        let axis = edge / 4;
+        [(axis + 1) % 3, (axis + 2) % 3].map(|other| other * 2 + (lower >> other & 1))
+    };
+    let (a, b) = (faces(a), faces(b));
+    a.iter().any(|face| b.contains(face))
+}
+
+fn vertex_position(mesh: &GridData, vertex: u32) -> Vec3 {
+    let i = vertex as usize * 3;
+    (mesh.vertices[i], mesh.vertices[i + 1], mesh.vertices[i + 2])
+}
+
+fn distance_squared(mesh: &GridData, a: u32, b: u32) -> f32 {
+    let offset = sub(vertex_position(mesh, a), vertex_position(mesh, b));
+    dot(offset, offset)
+}
+
+/// Triangulates a loop as a disk.
+///
+/// Loops up to hexagons are fanned from the vertex with the shortest
+/// diagonals that don't run along a face. Longer loops, and those without
+/// such a vertex, are fanned around an extra vertex in their middle.
+fn fill<F: ScalarField + ?Sized>(lattice: &mut Lattice<F>, boundary: &[Crossing]) {
+    let n = boundary.len();
+    let mesh = &mut lattice.mesh;
+    if n == 3 {
+        mesh.indices
+            .extend(boundary.iter().map(|&(_, vertex)| vertex));
+        return;
+    }
+
+    let candidates = if n <= 6 { 0..n } else { 0..0 };
+    let apex = candidates
+        .filter_map(|k| {
+            let mut length = 0.0;
+            for j in 2..n - 1 {
+                let (edge, vertex) = boundary[(k + j) % n];
+                if share_face(boundary[k].0, edge) {
+                    return None;
+                }
+                length += distance_squared(mesh, boundary[k].1, vertex);
+            }
+            Some((k, length))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+
+    if let Some((k, _)) = apex {
+        for j in 1..n - 1 {
+            mesh.indices.extend_from_slice(&[
+                boundary[k].1,
+                boundary[(k + j) % n].1,
+                boundary[(k + j + 1) % n].1,
+            ]);
+        }
+        return;
+    }
+
+    let sum = boundary.iter().fold((0.0, 0.0, 0.0), |sum, &(_, vertex)| {
+        add(sum, vertex_position(mesh, vertex))
+    });
+    let center = lattice.push_vertex(scale(sum, 1.0 / n as f32), None);
+    for k in 0..n {
+        lattice
+            .mesh
+            .indices
+            .extend_from_slice(&[center, boundary[k].1, boundary[(k + 1) % n].1]);
+    }
+}
+
+/// Joins two loops with a tube of triangles.
+///
+/// The loops run in opposite directions around the tube, so the strip walks
+/// forward along `a` and backward along `b`, one triangle per step. Of all
+/// the strips, the one with the shortest diagonals is taken, avoiding
+/// diagonals along a face. When the loops run along the same face that
+/// can't always be done, and the diagonals are instead split by a ring of
+/// extra vertices pulled towards the `center` of the cell.
+fn stitch<F: ScalarField + ?Sized>(
+    lattice: &mut Lattice<F>,
+    center: Vec3,
+    a: &[Crossing],
+    b: &[Crossing],
+) {
+    let (m, n) = (a.len(), b.len());
+    let at = |(start_a, start_b): (usize, usize), i: usize, j: usize| {
+        (a[(start_a + i) % m], b[(start_b + n - j % n) % n])
+    };
+    let mesh = &lattice.mesh;
+    let diagonal = |start, i, j| {
+        let ((edge_a, vertex_a), (edge_b, vertex_b)) = at(start, i, j);
+        let length = distance_squared(mesh, vertex_a, vertex_b);
+        if share_face(edge_a, edge_b) {
+            length + 1e3 * (1.0 + length)
+        } else {
+            length
+        }
+    };
+
+    // Cheapest strip from each pair of starting vertices, as the total
+    // length of its diagonals after `i` steps along `a` and `j` along `b`.
+    // Taking every step along one loop at the same vertex of the other
+    // would reuse the starting diagonal and pinch the tube, which starting
+    // with a step along `a`, ending with one along `b` and never passing
+    // through `(m, 0)` rules out.
+    let state = |i: usize, j: usize| i * (n + 1) + j;
+    let mut best: Option<(f32, (usize, usize), Vec<f32>)> = None;
+    for start in (0..m).flat_map(|i| (0..n).map(move |j| (i, j))) {
+        let mut cost = vec![f32::INFINITY; (m + 1) * (n + 1)];
+        cost[state(1, 0)] = diagonal(start, 0, 0) + diagonal(start, 1, 0);
+        for i in 1..=m {
+            for j in 0..=n {
+                if (i, j) == (1, 0) || (i, j) == (m, 0) {
+                    continue;
+                }
+                let along_b = if j > 0 {
+                    cost[state(i, j - 1)]
+                } else {
+                    f32::INFINITY
+                };
+                cost[state(i, j)] = if (i, j) == (m, n) {
+                    // Back at the starting diagonal
+                    along_b
+                } else {
+                    let along_a = if i > 1 {
+                        cost[state(i - 1, j)]
+                    } else {
+                        f32::INFINITY
+                    };
+                    along_a.min(along_b) + diagonal(start, i, j)
+                };
+            }
+        }
+
+        let total = cost[state(m, n)];
+        if best.as_ref().is_none_or(|(least, _, _)| total < *least) {
+            best = Some((total, start, cost));
+        }
+    }
+    let Some((_, start, cost)) = best else {
+        return;
+    };
+
+    // Walk the cheapest strip back from the end, as the diagonals it passes
+    let mut path = vec![(m, n)];
+    let (mut i, mut j) = (m, n);
+    while (i, j) != (0, 0) {
+        if (i, j) == (m, n) || (j > 0 && cost[state(i, j - 1)] <= cost[state(i - 1, j)]) {
+            j -= 1;
+        } else {
+            i -= 1;
+        }
+        path.push((i, j));
+    }
+    path.reverse();
+
+    let along_face = path.iter().any(|&(i, j)| {
+        let ((edge_a, _), (edge_b, _)) = at(start, i, j);
+        share_face(edge_a, edge_b)
+    });
+    let ring: Vec<u32> = if along_face {
+        path[..path.len() - 1]
+            .iter()
+            .map(|&(i, j)| {
+                let ((_, vertex_a), (_, vertex_b)) = at(start, i, j);
+                let midpoint = scale(
+                    add(
+                        vertex_position(&lattice.mesh, vertex_a),
+                        vertex_position(&lattice.mesh, vertex_b),
+                    ),
+                    0.5,
+                );
+                lattice.push_vertex(scale(add(midpoint, center), 0.5), None)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    for (k, step) in path.windows(2).enumerate() {
+        let ((i, j), (next_i, next_j)) = (step[0], step[1]);
+        let ((_, vertex_a), (_, vertex_b)) = at(start, i, j);
+        let ((_, next_a), (_, next_b)) = at(start, next_i, next_j);
+
+        // The triangle of the step, and the vertices splitting its
+        // diagonals if there is a ring
+        let (first, second, opposite) = if next_i > i {
+            (vertex_a, next_a, vertex_b)
+        } else {
+            (next_b, vertex_b, vertex_a)
+        };
+        let triangles = match ring.as_slice() {
+            [] => vec![first, second, opposite],
+            ring => {
+                let (split, next_split) = (ring[k], ring[(k + 1) % ring.len()]);
+                // Splits of the diagonals ending in `first` and `second`
+                let (split_first, split_second) = if next_i > i {
+                    (split, next_split)
+                } else {
+                    (next_split, split)
+                };
+                vec![
+                    first,
+                    second,
+                    split_second,
+                    first,
+                    split_second,
+                    split_first,
+                    split_first,
+                    split_second,
+                    opposite,
+                ]
+            }
+        };
+        lattice.mesh.indices.extend_from_slice(&triangles);
+    }
+}
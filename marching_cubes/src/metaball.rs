@@ -0,0 +1,414 @@
+use crate::field::{Bounds, ScalarField};
+use crate::vector::{closest_on_segment, closest_on_triangle, sub, Vec3};
+
+/// Falloff curve of a metaball's contribution over distance.
+///
+/// `radius` keeps the meaning it has for the hybrid kernel: the kernels with
+/// finite support reach zero at `sqrt(radius)` from the center, and all
+/// kernels are parameterized by the normalized squared distance
+/// `s = d² / radius`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Kernel {
+    /// `influence / (d² + radius)` faded out by a quintic smoothstep.
+    #[default]
+    Hybrid,
+    /// Blinn's "blobby molecules": `influence * exp(-blobbiness * s)`.
+    Blinn { blobbiness: f32 },
+    /// Wyvill's soft objects: `influence * (1 - 22/9 s + 17/9 s² - 4/9 s³)`.
+    Wyvill,
+    /// Murakami and Nishimura's metaballs: `influence * (1 - s)²`.
+    Murakami,
+    /// Classic point charge: `influence / s`, never reaching zero.
+    InverseSquare,
+}
+
+/// What a metaball measures distance to. The first point is always the
+/// ball's own `(x, y, z)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Skeleton {
+    /// A regular ball around its center.
+    #[default]
+    Point,
+    /// A meta-capsule around the segment from the center to `end`.
+    Segment { end: (f32, f32, f32) },
+    /// A slab around the triangle spanned by the center, `b` and `c`.
+    Triangle {
+        b: (f32, f32, f32),
+        c: (f32, f32, f32),
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Metaball {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub radius: f32,
+    pub influence: f32,
+    pub kernel: Kernel,
+    /// Subtract the contribution from the field instead of adding it, to
+    /// carve dents and pinches into the other balls.
+    pub negative: bool,
+    /// Stretch of the ball along its local axes, `(1, 1, 1)` for a sphere.
+    pub scale: (f32, f32, f32),
+    /// Rotation from the ball's local frame to world space, row-major.
+    pub rotation: [[f32; 3]; 3],
+    pub skeleton: Skeleton,
+    /// `rgba` color, blended across balls by their influence.
+    pub color: (f32, f32, f32, f32),
+}
+
+const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Rotation matrix of the unit quaternion `(x, y, z, w)`.
+pub fn quaternion_to_matrix(rotation: (f32, f32, f32, f32)) -> [[f32; 3]; 3] {
+    let (x, y, z, w) = rotation;
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+impl Metaball {
+    pub fn new(x: f32, y: f32, z: f32, radius: f32, influence: f32) -> Metaball {
+        Metaball::with_kernel(x, y, z, radius, influence, Kernel::Hybrid)
+    }
+
+    pub fn with_kernel(
+        x: f32,
+        y: f32,
+        z: f32,
+        radius: f32,
+        influence: f32,
+        kernel: Kernel,
+    ) -> Metaball {
+        Metaball {
+            x,
+            y,
+            z,
+            radius,
+            influence,
+            kernel,
+            negative: false,
+            scale: (1.0, 1.0, 1.0),
+            rotation: IDENTITY,
+            skeleton: Skeleton::Point,
+            color: (1.0, 1.0, 1.0, 1.0),
+        }
+    }
+
+    /// A meta-capsule: the falloff is measured from the segment `a`-`b`
+    /// instead of a single point.
+    pub fn capsule(
+        a: (f32, f32, f32),
+        b: (f32, f32, f32),
+        radius: f32,
+        influence: f32,
+    ) -> Metaball {
+        Metaball {
+            skeleton: Skeleton::Segment { end: b },
+            ..Metaball::new(a.0, a.1, a.2, radius, influence)
+        }
+    }
+
+    /// A meta-triangle: the falloff is measured from the triangle `a`-`b`-`c`.
+    pub fn triangle(
+        a: (f32, f32, f32),
+        b: (f32, f32, f32),
+        c: (f32, f32, f32),
+        radius: f32,
+        influence: f32,
+    ) -> Metaball {
+        Metaball {
+            skeleton: Skeleton::Triangle { b, c },
+            ..Metaball::new(a.0, a.1, a.2, radius, influence)
+        }
+    }
+
+    /// A ball stretched by `scale` along its local axes and oriented by the
+    /// unit quaternion `rotation`, given as `(x, y, z, w)`.
+    pub fn ellipsoid(
+        x: f32,
+        y: f32,
+        z: f32,
+        radius: f32,
+        influence: f32,
+        scale: (f32, f32, f32),
+        rotation: (f32, f32, f32, f32),
+    ) -> Metaball {
+        Metaball {
+            scale,
+            rotation: quaternion_to_matrix(rotation),
+            ..Metaball::new(x, y, z, radius, influence)
+        }
+    }
+
+    /// A ball that carves into the field rather than adding to it.
+    pub fn negative(x: f32, y: f32, z: f32, radius: f32, influence: f32) -> Metaball {
+        Metaball {
+            negative: true,
+            ..Metaball::new(x, y, z, radius, influence)
+        }
+    }
+
+    /// `+1` for regular balls, `-1` for negative ones.
+    fn sign(&self) -> f32 {
+        if self.negative {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Kernel value at squared distance `distance_squared` together with its
+    /// derivative with respect to `distance_squared`.
+    fn falloff(&self, distance_squared: f32) -> (f32, f32) {
+        let radius = self.radius;
+        let s = distance_squared / radius;
+
+        let (value, derivative) = match self.kernel {
+            Kernel::Hybrid => {
+                let u = s.sqrt();
+                if u >= 1.0 {
+                    return (0.0, 0.0);
+                }
+
+                // Hermite cubic interpolation for blending
+                let denominator = distance_squared + radius;
+                let smooth_factor = 1.0 - u * u * u * (u * (u * 6.0 - 15.0) + 10.0);
+                // d(smooth_factor)/d(d²), the 1 / u from du/d(d²) cancels against
+                // the u² of the derivative
+                let smooth_derivative = -15.0 * u * (1.0 - u) * (1.0 - u) / radius;
+
+                (
+                    smooth_factor / denominator,
+                    smooth_derivative / denominator - smooth_factor / (denominator * denominator),
+                )
+            }
+            Kernel::Blinn { blobbiness } => {
+                let value = (-blobbiness * s).exp();
+                (value, -blobbiness * value / radius)
+            }
+            Kernel::Wyvill => {
+                if s >= 1.0 {
+                    return (0.0, 0.0);
+                }
+
+                (
+                    1.0 + s * (-22.0 / 9.0 + s * (17.0 / 9.0 - s * 4.0 / 9.0)),
+                    (-22.0 / 9.0 + s * (34.0 / 9.0 - s * 12.0 / 9.0)) / radius,
+                )
+            }
+            Kernel::Murakami => {
+                if s >= 1.0 {
+                    return (0.0, 0.0);
+                }
+
+                ((1.0 - s) * (1.0 - s), -2.0 * (1.0 - s) / radius)
+            }
+            Kernel::InverseSquare => {
+                // Keep the center finite
+                let s = s.max(f32::EPSILON);
+                (1.0 / s, -1.0 / (s * s * radius))
+            }
+        };
+
+        let weight = self.sign() * self.influence;
+        (weight * value, weight * derivative)
+    }
+
+    /// Maps a world-space point into the ball's local frame, relative to the
+    /// center and divided by the scale, so the ellipsoid becomes a unit sphere.
+    fn local_point(&self, x: f32, y: f32, z: f32) -> Vec3 {
+        let (dx, dy, dz) = (x - self.x, y - self.y, z - self.z);
+        let r = &self.rotation;
+
+        // Inverse rotation, i.e. the transpose
+        (
+            (r[0][0] * dx + r[1][0] * dy + r[2][0] * dz) / self.scale.0,
+            (r[0][1] * dx + r[1][1] * dy + r[2][1] * dz) / self.scale.1,
+            (r[0][2] * dx + r[1][2] * dy + r[2][2] * dz) / self.scale.2,
+        )
+    }
+
+    /// Offset of a point from the nearest point of the skeleton, in the local
+    /// frame.
+    fn local_offset(&self, x: f32, y: f32, z: f32) -> Vec3 {
+        let p = self.local_point(x, y, z);
+
+        let closest = match self.skeleton {
+            Skeleton::Point => return p,
+            Skeleton::Segment { end } => {
+                closest_on_segment(p, (0.0, 0.0, 0.0), self.local_point(end.0, end.1, end.2))
+            }
+            Skeleton::Triangle { b, c } => closest_on_triangle(
+                p,
+                (0.0, 0.0, 0.0),
+                self.local_point(b.0, b.1, b.2),
+                self.local_point(c.0, c.1, c.2),
+            ),
+        };
+
+        sub(p, closest)
+    }
+
+    fn contribution(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (lx, ly, lz) = self.local_offset(x, y, z);
+
+        self.falloff(lx * lx + ly * ly + lz * lz).0
+    }
+
+    /// Closed-form derivative of `contribution`. The kernel only depends on
+    /// the squared local distance `d²` to the skeleton, whose gradient is the
+    /// local offset divided by the scale once more and rotated back to world
+    /// space.
+    fn contribution_gradient(&self, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+        let (lx, ly, lz) = self.local_offset(x, y, z);
+
+        let (_, d_ds) = self.falloff(lx * lx + ly * ly + lz * lz);
+        let (gx, gy, gz) = (
+            2.0 * d_ds * lx / self.scale.0,
+            2.0 * d_ds * ly / self.scale.1,
+            2.0 * d_ds * lz / self.scale.2,
+        );
+
+        let r = &self.rotation;
+        (
+            r[0][0] * gx + r[0][1] * gy + r[0][2] * gz,
+            r[1][0] * gx + r[1][1] * gy + r[1][2] * gz,
+            r[2][0] * gx + r[2][1] * gy + r[2][2] * gz,
+        )
+    }
+
+    /// The kernels with finite support reach zero at `sqrt(radius)` from the
+    /// center in the local frame, the others have no bounds.
+    fn bounds(&self) -> Option<Bounds> {
+        if self.negative {
+            return None;
+        }
+
+        match self.kernel {
+            Kernel::Blinn { .. } | Kernel::InverseSquare => None,
+            Kernel::Hybrid | Kernel::Wyvill | Kernel::Murakami => {
+                let reach = self.radius.sqrt();
+                let axes = (
+                    reach * self.scale.0,
+                    reach * self.scale.1,
+                    reach * self.scale.2,
+                );
+                // Half extents of the rotated ellipsoid's bounding box
+                let extent = |row: [f32; 3]| {
+                    ((row[0] * axes.0).powi(2)
+                        + (row[1] * axes.1).powi(2)
+                        + (row[2] * axes.2).powi(2))
+                    .sqrt()
+                };
+                let (ex, ey, ez) = (
+                    extent(self.rotation[0]),
+                    extent(self.rotation[1]),
+                    extent(self.rotation[2]),
+                );
+
+                // The ellipsoid swept over every point of the skeleton
+                let around = |p: Vec3| {
+                    Bounds::new(
+                        (p.0 - ex, p.1 - ey, p.2 - ez),
+                        (p.0 + ex, p.1 + ey, p.2 + ez),
+                    )
+                };
+                let bounds = around((self.x, self.y, self.z));
+
+                Some(match self.skeleton {
+                    Skeleton::Point => bounds,
+                    Skeleton::Segment { end } => bounds.union(&around(end)),
+                    Skeleton::Triangle { b, c } => bounds.union(&around(b)).union(&around(c)),
+                })
+            }
+        }
+    }
+}
+
+impl ScalarField for Metaball {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.contribution(x, y, z)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        Some(self.contribution_gradient(x, y, z))
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        Metaball::bounds(self)
+    }
+
+    fn color(&self, _x: f32, _y: f32, _z: f32) -> Option<(f32, f32, f32, f32)> {
+        Some(self.color)
+    }
+}
+
+/// A set of metaballs blends by summing every ball's contribution.
+impl ScalarField for [Metaball] {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.iter()
+            .fold(0.0, |sum, ball| sum + ball.contribution(x, y, z))
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        Some(self.iter().fold((0.0, 0.0, 0.0), |sum, ball| {
+            let (gx, gy, gz) = ball.contribution_gradient(x, y, z);
+            (sum.0 + gx, sum.1 + gy, sum.2 + gz)
+        }))
+    }
+
+    /// Negative balls only ever remove from the field, so the set is bounded
+    /// by its regular balls alone.
+    fn bounds(&self) -> Option<Bounds> {
+        let mut balls = self.iter().filter(|ball| !ball.negative);
+        let first = balls.next()?.bounds()?;
+        balls.try_fold(first, |acc, ball| Some(acc.union(&ball.bounds()?)))
+    }
+
+    /// Colors of the regular balls, weighted by how much each contributes at
+    /// the point. Negative balls only carve and don't tint the surface.
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let mut total = 0.0;
+        let mut color = (0.0, 0.0, 0.0, 0.0);
+
+        for ball in self.iter().filter(|ball| !ball.negative) {
+            let weight = ball.contribution(x, y, z).max(0.0);
+            total += weight;
+            color.0 += ball.color.0 * weight;
+            color.1 += ball.color.1 * weight;
+            color.2 += ball.color.2 * weight;
+            color.3 += ball.color.3 * weight;
+        }
+
+        if total <= 0.0 {
+            // Past every ball's reach, e.g. on caps of closed meshes
+            return self
+                .iter()
+                .find(|ball| !ball.negative)
+                .map(|ball| ball.color);
+        }
+
+        Some((
+            color.0 / total,
+            color.1 / total,
+            color.2 / total,
+            color.3 / total,
+        ))
+    }
+}
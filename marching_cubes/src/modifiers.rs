@@ -0,0 +1,463 @@
+use crate::field::{gradient_of, Bounds, ScalarField};
+use crate::metaball::quaternion_to_matrix;
+
+type Matrix = [[f32; 3]; 3];
+
+fn multiply(m: &Matrix, v: (f32, f32, f32)) -> (f32, f32, f32) {
+    (
+        m[0][0] * v.0 + m[0][1] * v.1 + m[0][2] * v.2,
+        m[1][0] * v.0 + m[1][1] * v.1 + m[1][2] * v.2,
+        m[2][0] * v.0 + m[2][1] * v.1 + m[2][2] * v.2,
+    )
+}
+
+fn multiply_transposed(m: &Matrix, v: (f32, f32, f32)) -> (f32, f32, f32) {
+    (
+        m[0][0] * v.0 + m[1][0] * v.1 + m[2][0] * v.2,
+        m[0][1] * v.0 + m[1][1] * v.1 + m[2][1] * v.2,
+        m[0][2] * v.0 + m[1][2] * v.1 + m[2][2] * v.2,
+    )
+}
+
+/// Inverse of `m`, or `None` when it's singular or the inverse overflows.
+fn invert(m: &Matrix) -> Option<Matrix> {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let determinant = m[0][0] * cofactor(1, 2, 1, 2) - m[0][1] * cofactor(1, 2, 0, 2)
+        + m[0][2] * cofactor(1, 2, 0, 1);
+    let d = 1.0 / determinant;
+
+    let inverse = [
+        [
+            cofactor(1, 2, 1, 2) * d,
+            -cofactor(0, 2, 1, 2) * d,
+            cofactor(0, 1, 1, 2) * d,
+        ],
+        [
+            -cofactor(1, 2, 0, 2) * d,
+            cofactor(0, 2, 0, 2) * d,
+            -cofactor(0, 1, 0, 2) * d,
+        ],
+        [
+            cofactor(1, 2, 0, 1) * d,
+            -cofactor(0, 2, 0, 1) * d,
+            cofactor(0, 1, 0, 1) * d,
+        ],
+    ];
+    inverse
+        .iter()
+        .flatten()
+        .all(|entry| entry.is_finite())
+        .then_some(inverse)
+}
+
+/// Bounds of `bounds` after mapping every corner through `map`.
+fn map_corners(bounds: &Bounds, map: impl Fn((f32, f32, f32)) -> (f32, f32, f32)) -> Bounds {
+    let (min, max) = (bounds.min, bounds.max);
+    let mut corners = (0..8).map(|i| {
+        map((
+            if i & 1 == 0 { min.0 } else { max.0 },
+            if i & 2 == 0 { min.1 } else { max.1 },
+            if i & 4 == 0 { min.2 } else { max.2 },
+        ))
+    });
+
+    let first = corners.next().unwrap();
+    corners.fold(Bounds::new(first, first), |acc, p| {
+        acc.union(&Bounds::new(p, p))
+    })
+}
+
+/// Largest distance of the box from the axis running along `axis`.
+fn radius_around(bounds: &Bounds, axis: usize) -> f32 {
+    let reach = |min: f32, max: f32| min.abs().max(max.abs());
+    let (x, y, z) = (
+        reach(bounds.min.0, bounds.max.0),
+        reach(bounds.min.1, bounds.max.1),
+        reach(bounds.min.2, bounds.max.2),
+    );
+
+    match axis {
+        0 => (y * y + z * z).sqrt(),
+        1 => (x * x + z * z).sqrt(),
+        _ => (x * x + y * y).sqrt(),
+    }
+}
+
+/// Places a field with an affine transform: `p' = matrix * p + translation`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform<F> {
+    pub field: F,
+    matrix: Matrix,
+    inverse: Matrix,
+    translation: (f32, f32, f32),
+}
+
+impl<F: ScalarField> Transform<F> {
+    /// Scales, then rotates by the unit quaternion `(x, y, z, w)`, then
+    /// translates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transform collapses space, like a zero scale does; see
+    /// [`Transform::try_new`].
+    pub fn new(
+        field: F,
+        translation: (f32, f32, f32),
+        rotation: (f32, f32, f32, f32),
+        scale: (f32, f32, f32),
+    ) -> Transform<F> {
+        Transform::try_new(field, translation, rotation, scale)
+            .expect("expected an invertible transform")
+    }
+
+    /// [`Transform::new`], or `None` if the transform collapses space.
+    pub fn try_new(
+        field: F,
+        translation: (f32, f32, f32),
+        rotation: (f32, f32, f32, f32),
+        scale: (f32, f32, f32),
+    ) -> Option<Transform<F>> {
+        let mut matrix = quaternion_to_matrix(rotation);
+        for row in matrix.iter_mut() {
+            row[0] *= scale.0;
+            row[1] *= scale.1;
+            row[2] *= scale.2;
+        }
+
+        Transform::try_from_matrix(field, matrix, translation)
+    }
+
+    /// General affine transform, `matrix` being row-major.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `matrix` isn't invertible; see
+    /// [`Transform::try_from_matrix`].
+    pub fn from_matrix(field: F, matrix: Matrix, translation: (f32, f32, f32)) -> Transform<F> {
+        Transform::try_from_matrix(field, matrix, translation)
+            .expect("expected an invertible matrix")
+    }
+
+    /// [`Transform::from_matrix`], or `None` if `matrix` isn't invertible.
+    pub fn try_from_matrix(
+        field: F,
+        matrix: Matrix,
+        translation: (f32, f32, f32),
+    ) -> Option<Transform<F>> {
+        Some(Transform {
+            field,
+            matrix,
+            inverse: invert(&matrix)?,
+            translation,
+        })
+    }
+
+    fn local(&self, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+        let t = self.translation;
+        multiply(&self.inverse, (x - t.0, y - t.1, z - t.2))
+    }
+}
+
+impl<F: ScalarField> ScalarField for Transform<F> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (lx, ly, lz) = self.local(x, y, z);
+        self.field.value(lx, ly, lz)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let (lx, ly, lz) = self.local(x, y, z);
+        let gradient = gradient_of(&self.field, lx, ly, lz);
+        Some(multiply_transposed(&self.inverse, gradient))
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let t = self.translation;
+        Some(map_corners(&self.field.bounds()?, |p| {
+            let p = multiply(&self.matrix, p);
+            (p.0 + t.0, p.1 + t.1, p.2 + t.2)
+        }))
+    }
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let (lx, ly, lz) = self.local(x, y, z);
+        self.field.color(lx, ly, lz)
+    }
+}
+
+/// Repeats a field on a lattice with spacing `period`, centered on the origin.
+///
+/// An axis with a period of zero isn't repeated. With `limit` set, there are
+/// only `limit` extra copies on either side of the original along each axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Repeat<F> {
+    pub field: F,
+    pub period: (f32, f32, f32),
+    pub limit: Option<(u32, u32, u32)>,
+}
+
+impl<F: ScalarField> Repeat<F> {
+    pub fn new(field: F, period: (f32, f32, f32), limit: Option<(u32, u32, u32)>) -> Repeat<F> {
+        Repeat {
+            field,
+            period,
+            limit,
+        }
+    }
+
+    fn local(&self, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+        let wrap = |v: f32, period: f32, limit: Option<u32>| {
+            if period == 0.0 {
+                return v;
+            }
+
+            let mut cell = (v / period).round();
+            if let Some(limit) = limit {
+                cell = cell.clamp(-(limit as f32), limit as f32);
+            }
+            v - period * cell
+        };
+        let limit = self.limit;
+
+        (
+            wrap(x, self.period.0, limit.map(|l| l.0)),
+            wrap(y, self.period.1, limit.map(|l| l.1)),
+            wrap(z, self.period.2, limit.map(|l| l.2)),
+        )
+    }
+}
+
+impl<F: ScalarField> ScalarField for Repeat<F> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (lx, ly, lz) = self.local(x, y, z);
+        self.field.value(lx, ly, lz)
+    }
+
+    /// Repetition is a translation within each copy, gradients pass through.
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let (lx, ly, lz) = self.local(x, y, z);
+        Some(gradient_of(&self.field, lx, ly, lz))
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let bounds = self.field.bounds()?;
+        let (px, py, pz) = self.period;
+        let (lx, ly, lz) = match self.limit {
+            Some(limit) => limit,
+            None if px == 0.0 && py == 0.0 && pz == 0.0 => (0, 0, 0),
+            None => return None,
+        };
+        let (ex, ey, ez) = (px * lx as f32, py * ly as f32, pz * lz as f32);
+
+        Some(Bounds::new(
+            (bounds.min.0 - ex, bounds.min.1 - ey, bounds.min.2 - ez),
+            (bounds.max.0 + ex, bounds.max.1 + ey, bounds.max.2 + ez),
+        ))
+    }
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let (lx, ly, lz) = self.local(x, y, z);
+        self.field.color(lx, ly, lz)
+    }
+}
+
+/// Mirrors the positive side of a field across the coordinate planes of the
+/// selected axes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mirror<F> {
+    pub field: F,
+    pub axes: (bool, bool, bool),
+}
+
+impl<F: ScalarField> Mirror<F> {
+    pub fn new(field: F, axes: (bool, bool, bool)) -> Mirror<F> {
+        Mirror { field, axes }
+    }
+
+    /// Point on the mirrored side, with the sign applied on each axis.
+    fn local(&self, x: f32, y: f32, z: f32) -> ((f32, f32, f32), (f32, f32, f32)) {
+        let fold = |v: f32, mirrored: bool| {
+            if mirrored && v < 0.0 {
+                (-v, -1.0)
+            } else {
+                (v, 1.0)
+            }
+        };
+        let (x, sx) = fold(x, self.axes.0);
+        let (y, sy) = fold(y, self.axes.1);
+        let (z, sz) = fold(z, self.axes.2);
+
+        ((x, y, z), (sx, sy, sz))
+    }
+}
+
+impl<F: ScalarField> ScalarField for Mirror<F> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        let ((lx, ly, lz), _) = self.local(x, y, z);
+        self.field.value(lx, ly, lz)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let ((lx, ly, lz), (sx, sy, sz)) = self.local(x, y, z);
+        let (gx, gy, gz) = gradient_of(&self.field, lx, ly, lz);
+        Some((gx * sx, gy * sy, gz * sz))
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let bounds = self.field.bounds()?;
+        let symmetric = |min: f32, max: f32, mirrored: bool| {
+            if mirrored {
+                // Only the positive side is kept and copied over
+                let reach = max.max(0.0);
+                (-reach, reach)
+            } else {
+                (min, max)
+            }
+        };
+        let x = symmetric(bounds.min.0, bounds.max.0, self.axes.0);
+        let y = symmetric(bounds.min.1, bounds.max.1, self.axes.1);
+        let z = symmetric(bounds.min.2, bounds.max.2, self.axes.2);
+
+        Some(Bounds::new((x.0, y.0, z.0), (x.1, y.1, z.1)))
+    }
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let ((lx, ly, lz), _) = self.local(x, y, z);
+        self.field.color(lx, ly, lz)
+    }
+}
+
+/// Twists a field around the y axis by `rate` radians per unit of height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Twist<F> {
+    pub field: F,
+    pub rate: f32,
+}
+
+impl<F: ScalarField> Twist<F> {
+    pub fn new(field: F, rate: f32) -> Twist<F> {
+        Twist { field, rate }
+    }
+
+    fn local(&self, x: f32, y: f32, z: f32) -> ((f32, f32, f32), (f32, f32)) {
+        let (s, c) = (self.rate * y).sin_cos();
+        ((c * x - s * z, y, s * x + c * z), (s, c))
+    }
+}
+
+impl<F: ScalarField> ScalarField for Twist<F> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        let ((lx, ly, lz), _) = self.local(x, y, z);
+        self.field.value(lx, ly, lz)
+    }
+
+    /// Chain rule through the rotation, which itself depends on `y`.
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let ((lx, ly, lz), (s, c)) = self.local(x, y, z);
+        let (gx, gy, gz) = gradient_of(&self.field, lx, ly, lz);
+        let k = self.rate;
+
+        Some((
+            c * gx + s * gz,
+            gy - k * lz * gx + k * lx * gz,
+            -s * gx + c * gz,
+        ))
+    }
+
+    /// Twisting keeps the distance from the y axis.
+    fn bounds(&self) -> Option<Bounds> {
+        let bounds = self.field.bounds()?;
+        let r = radius_around(&bounds, 1);
+        Some(Bounds::new((-r, bounds.min.1, -r), (r, bounds.max.1, r)))
+    }
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let ((lx, ly, lz), _) = self.local(x, y, z);
+        self.field.color(lx, ly, lz)
+    }
+}
+
+/// Bends a field in the xy plane by `rate` radians per unit along x.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bend<F> {
+    pub field: F,
+    pub rate: f32,
+}
+
+impl<F: ScalarField> Bend<F> {
+    pub fn new(field: F, rate: f32) -> Bend<F> {
+        Bend { field, rate }
+    }
+
+    fn local(&self, x: f32, y: f32, z: f32) -> ((f32, f32, f32), (f32, f32)) {
+        let (s, c) = (self.rate * x).sin_cos();
+        ((c * x - s * y, s * x + c * y, z), (s, c))
+    }
+}
+
+impl<F: ScalarField> ScalarField for Bend<F> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        let ((lx, ly, lz), _) = self.local(x, y, z);
+        self.field.value(lx, ly, lz)
+    }
+
+    /// Chain rule through the rotation, which itself depends on `x`.
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let ((lx, ly, lz), (s, c)) = self.local(x, y, z);
+        let (gx, gy, gz) = gradient_of(&self.field, lx, ly, lz);
+        let k = self.rate;
+
+        Some(((c - k * ly) * gx + (s + k * lx) * gy, -s * gx + c * gy, gz))
+    }
+
+    /// Bending keeps the distance from the z axis.
+    fn bounds(&self) -> Option<Bounds> {
+        let bounds = self.field.bounds()?;
+        let r = radius_around(&bounds, 2);
+        Some(Bounds::new((-r, -r, bounds.min.2), (r, r, bounds.max.2)))
+    }
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        let ((lx, ly, lz), _) = self.local(x, y, z);
+        self.field.color(lx, ly, lz)
+    }
+}
+
+/// Adds `amount` times another field to a field, e.g. noise to roughen up a
+/// primitive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Displace<F, D> {
+    pub field: F,
+    pub displacement: D,
+    pub amount: f32,
+}
+
+impl<F: ScalarField, D: ScalarField> Displace<F, D> {
+    pub fn new(field: F, displacement: D, amount: f32) -> Displace<F, D> {
+        Displace {
+            field,
+            displacement,
+            amount,
+        }
+    }
+}
+
+impl<F: ScalarField, D: ScalarField> ScalarField for Displace<F, D> {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.field.value(x, y, z) + self.amount * self.displacement.value(x, y, z)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let (fx, fy, fz) = gradient_of(&self.field, x, y, z);
+        let (dx, dy, dz) = gradient_of(&self.displacement, x, y, z);
+        let k = self.amount;
+
+        Some((fx + k * dx, fy + k * dy, fz + k * dz))
+    }
+
+    // No bounds: how far the displacement pushes the surface out is unknown
+
+    fn color(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32, f32)> {
+        self.field.color(x, y, z)
+    }
+}
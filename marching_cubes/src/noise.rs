@@ -0,0 +1,370 @@
+use crate::field::ScalarField;
+
+/// Single-octave noise function a [`Noise`] field is built from.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Basis {
+    /// Ken Perlin's improved gradient noise.
+    #[default]
+    Perlin,
+    /// Simplex noise, cheaper than Perlin noise and without its axis-aligned
+    /// artifacts.
+    Simplex,
+    /// Smoothly interpolated random values at the lattice points.
+    Value,
+    /// Cellular noise: distance to the nearest of one random feature point
+    /// per unit cell.
+    Worley,
+}
+
+/// How the octaves of a [`Noise`] field are summed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Fractal {
+    /// Fractal Brownian motion, the plain sum.
+    #[default]
+    Fbm,
+    /// Sum of `(1 - |n|)²`, giving sharp ridges along the zero crossings.
+    Ridged,
+    /// Sum of `|n|`, giving billowy creases.
+    Turbulence,
+}
+
+/// Seeded coherent noise, deterministic across platforms.
+///
+/// Each octave is `frequency * lacunarity^i` times finer and contributes
+/// `amplitude * gain^i`. Values are roughly within `[-1, 1]` per octave for
+/// the signed bases, so meshing at `0.0` gives caves and blobs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    pub basis: Basis,
+    pub fractal: Fractal,
+    pub seed: u32,
+    pub frequency: f32,
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub gain: f32,
+    pub amplitude: f32,
+}
+
+impl Noise {
+    /// A single octave of `basis`.
+    pub fn new(basis: Basis, seed: u32, frequency: f32) -> Noise {
+        Noise::fractal(basis, Fractal::Fbm, seed, frequency, 1, 2.0, 0.5)
+    }
+
+    pub fn fractal(
+        basis: Basis,
+        fractal: Fractal,
+        seed: u32,
+        frequency: f32,
+        octaves: u32,
+        lacunarity: f32,
+        gain: f32,
+    ) -> Noise {
+        Noise {
+            basis,
+            fractal,
+            seed,
+            frequency,
+            octaves,
+            lacunarity,
+            gain,
+            amplitude: 1.0,
+        }
+    }
+
+    /// Noise value together with its gradient.
+    pub fn sample(&self, x: f32, y: f32, z: f32) -> (f32, (f32, f32, f32)) {
+        let mut value = 0.0;
+        let mut gradient = (0.0, 0.0, 0.0);
+        let mut frequency = self.frequency;
+        let mut amplitude = self.amplitude;
+
+        for octave in 0..self.octaves {
+            let seed = self.seed.wrapping_add(octave.wrapping_mul(0x9e37_79b9));
+            let (n, (dx, dy, dz)) = basis(
+                self.basis,
+                seed,
+                x * frequency,
+                y * frequency,
+                z * frequency,
+            );
+
+            // Value and derivative of the octave's shaping function
+            let (shaped, slope) = match self.fractal {
+                Fractal::Fbm => (n, 1.0),
+                Fractal::Ridged => {
+                    let ridge = 1.0 - n.abs();
+                    (ridge * ridge, -2.0 * ridge * n.signum())
+                }
+                Fractal::Turbulence => (n.abs(), n.signum()),
+            };
+
+            value += amplitude * shaped;
+            let scale = amplitude * slope * frequency;
+            gradient.0 += scale * dx;
+            gradient.1 += scale * dy;
+            gradient.2 += scale * dz;
+
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+
+        (value, gradient)
+    }
+}
+
+impl ScalarField for Noise {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.sample(x, y, z).0
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        Some(self.sample(x, y, z).1)
+    }
+}
+
+fn basis(basis: Basis, seed: u32, x: f32, y: f32, z: f32) -> (f32, (f32, f32, f32)) {
+    match basis {
+        Basis::Perlin => lattice(seed, x, y, z, perlin_corner),
+        Basis::Value => lattice(seed, x, y, z, value_corner),
+        Basis::Simplex => simplex(seed, x, y, z),
+        Basis::Worley => worley(seed, x, y, z),
+    }
+}
+
+/// Integer hash of a lattice point, so noise only depends on the seed and
+/// not on a permutation table.
+fn hash(seed: u32, x: i32, y: i32, z: i32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+/// Maps a hash to `[0, 1)`.
+fn unit(h: u32) -> f32 {
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// The 12 cube edge directions of improved Perlin noise.
+fn gradient_direction(h: u32) -> (f32, f32, f32) {
+    const DIRECTIONS: [(f32, f32, f32); 12] = [
+        (1.0, 1.0, 0.0),
+        (-1.0, 1.0, 0.0),
+        (1.0, -1.0, 0.0),
+        (-1.0, -1.0, 0.0),
+        (1.0, 0.0, 1.0),
+        (-1.0, 0.0, 1.0),
+        (1.0, 0.0, -1.0),
+        (-1.0, 0.0, -1.0),
+        (0.0, 1.0, 1.0),
+        (0.0, -1.0, 1.0),
+        (0.0, 1.0, -1.0),
+        (0.0, -1.0, -1.0),
+    ];
+
+    DIRECTIONS[(h % 12) as usize]
+}
+
+/// Contribution of one lattice corner: its value and gradient, given the
+/// corner's hash and the offset from the corner to the sample point.
+type Corner = fn(u32, (f32, f32, f32)) -> (f32, (f32, f32, f32));
+
+/// Perlin corner: the gradient's ramp through the corner.
+fn perlin_corner(h: u32, offset: (f32, f32, f32)) -> (f32, (f32, f32, f32)) {
+    let g = gradient_direction(h);
+    (g.0 * offset.0 + g.1 * offset.1 + g.2 * offset.2, g)
+}
+
+/// Value corner: a flat random value.
+fn value_corner(h: u32, _offset: (f32, f32, f32)) -> (f32, (f32, f32, f32)) {
+    (unit(h) * 2.0 - 1.0, (0.0, 0.0, 0.0))
+}
+
+/// Quintic interpolation of the 8 corner contributions of the lattice cell
+/// around a point, with analytic derivatives.
+fn lattice(seed: u32, x: f32, y: f32, z: f32, corner: Corner) -> (f32, (f32, f32, f32)) {
+    let (ix, iy, iz) = (x.floor(), y.floor(), z.floor());
+    let (fx, fy, fz) = (x - ix, y - iy, z - iz);
+    let (ix, iy, iz) = (ix as i32, iy as i32, iz as i32);
+
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let fade_derivative = |t: f32| 30.0 * t * t * (t * (t - 2.0) + 1.0);
+    let (ux, uy, uz) = (fade(fx), fade(fy), fade(fz));
+    let (dux, duy, duz) = (
+        fade_derivative(fx),
+        fade_derivative(fy),
+        fade_derivative(fz),
+    );
+
+    let at = |cx: i32, cy: i32, cz: i32| {
+        corner(
+            hash(seed, ix + cx, iy + cy, iz + cz),
+            (fx - cx as f32, fy - cy as f32, fz - cz as f32),
+        )
+    };
+    let (va, ga) = at(0, 0, 0);
+    let (vb, gb) = at(1, 0, 0);
+    let (vc, gc) = at(0, 1, 0);
+    let (vd, gd) = at(1, 1, 0);
+    let (ve, ge) = at(0, 0, 1);
+    let (vf, gf) = at(1, 0, 1);
+    let (vg, gg) = at(0, 1, 1);
+    let (vh, gh) = at(1, 1, 1);
+
+    // Trilinear interpolation expanded into polynomial coefficients
+    let k0 = va;
+    let k1 = vb - va;
+    let k2 = vc - va;
+    let k3 = ve - va;
+    let k4 = va - vb - vc + vd;
+    let k5 = va - vc - ve + vg;
+    let k6 = va - vb - ve + vf;
+    let k7 = -va + vb + vc - vd + ve - vf - vg + vh;
+
+    let value = k0
+        + k1 * ux
+        + k2 * uy
+        + k3 * uz
+        + k4 * ux * uy
+        + k5 * uy * uz
+        + k6 * uz * ux
+        + k7 * ux * uy * uz;
+
+    // Same interpolation of the corner gradients, plus the fade's slope
+    let blend = |a: f32, b: f32, c: f32, d: f32, e: f32, f: f32, g: f32, h: f32| {
+        a + (b - a) * ux
+            + (c - a) * uy
+            + (e - a) * uz
+            + (a - b - c + d) * ux * uy
+            + (a - c - e + g) * uy * uz
+            + (a - b - e + f) * uz * ux
+            + (-a + b + c - d + e - f - g + h) * ux * uy * uz
+    };
+    let gradient = (
+        blend(ga.0, gb.0, gc.0, gd.0, ge.0, gf.0, gg.0, gh.0)
+            + dux * (k1 + k4 * uy + k6 * uz + k7 * uy * uz),
+        blend(ga.1, gb.1, gc.1, gd.1, ge.1, gf.1, gg.1, gh.1)
+            + duy * (k2 + k5 * uz + k4 * ux + k7 * uz * ux),
+        blend(ga.2, gb.2, gc.2, gd.2, ge.2, gf.2, gg.2, gh.2)
+            + duz * (k3 + k6 * ux + k5 * uy + k7 * ux * uy),
+    );
+
+    (value, gradient)
+}
+
+/// 3D simplex noise with analytic derivatives, after Stefan Gustavson.
+fn simplex(seed: u32, x: f32, y: f32, z: f32) -> (f32, (f32, f32, f32)) {
+    const SKEW: f32 = 1.0 / 3.0;
+    const UNSKEW: f32 = 1.0 / 6.0;
+
+    let s = (x + y + z) * SKEW;
+    let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+    let t = (i + j + k) * UNSKEW;
+    let origin = (x - (i - t), y - (j - t), z - (k - t));
+    let (i, j, k) = (i as i32, j as i32, k as i32);
+
+    // Which of the six tetrahedra of the skewed cube the point is in
+    let (first, second) = if origin.0 >= origin.1 {
+        if origin.1 >= origin.2 {
+            ((1, 0, 0), (1, 1, 0))
+        } else if origin.0 >= origin.2 {
+            ((1, 0, 0), (1, 0, 1))
+        } else {
+            ((0, 0, 1), (1, 0, 1))
+        }
+    } else if origin.1 < origin.2 {
+        ((0, 0, 1), (0, 1, 1))
+    } else if origin.0 < origin.2 {
+        ((0, 1, 0), (0, 1, 1))
+    } else {
+        ((0, 1, 0), (1, 1, 0))
+    };
+
+    let mut value = 0.0;
+    let mut gradient = (0.0, 0.0, 0.0);
+
+    for (n, (ci, cj, ck)) in [(0, 0, 0), first, second, (1, 1, 1)]
+        .into_iter()
+        .enumerate()
+    {
+        let unskew = n as f32 * UNSKEW;
+        let offset = (
+            origin.0 - ci as f32 + unskew,
+            origin.1 - cj as f32 + unskew,
+            origin.2 - ck as f32 + unskew,
+        );
+
+        // A squared radius of 0.5 keeps each corner's kernel within the
+        // simplices around it, the 0.6 often used leaves small jumps
+        let t = 0.5 - offset.0 * offset.0 - offset.1 * offset.1 - offset.2 * offset.2;
+        if t <= 0.0 {
+            continue;
+        }
+
+        let g = gradient_direction(hash(seed, i + ci, j + cj, k + ck));
+        let ramp = g.0 * offset.0 + g.1 * offset.1 + g.2 * offset.2;
+        let (t2, t4) = (t * t, t * t * t * t);
+
+        value += t4 * ramp;
+        // d(t⁴ ramp) = t⁴ g - 8 t³ ramp offset
+        let falloff = -8.0 * t2 * t * ramp;
+        gradient.0 += t4 * g.0 + falloff * offset.0;
+        gradient.1 += t4 * g.1 + falloff * offset.1;
+        gradient.2 += t4 * g.2 + falloff * offset.2;
+    }
+
+    // Brings the range to roughly [-1, 1]
+    const SCALE: f32 = 75.0;
+    (
+        SCALE * value,
+        (SCALE * gradient.0, SCALE * gradient.1, SCALE * gradient.2),
+    )
+}
+
+/// Worley F1 noise: distance to the nearest feature point.
+fn worley(seed: u32, x: f32, y: f32, z: f32) -> (f32, (f32, f32, f32)) {
+    let (ix, iy, iz) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+
+    let mut nearest = f32::MAX;
+    let mut offset = (0.0, 0.0, 0.0);
+
+    for cz in iz - 1..=iz + 1 {
+        for cy in iy - 1..=iy + 1 {
+            for cx in ix - 1..=ix + 1 {
+                let h = hash(seed, cx, cy, cz);
+                let feature = (
+                    cx as f32 + unit(h),
+                    cy as f32 + unit(hash(h, 1, 0, 0)),
+                    cz as f32 + unit(hash(h, 0, 1, 0)),
+                );
+                let d = (x - feature.0, y - feature.1, z - feature.2);
+                let distance_squared = d.0 * d.0 + d.1 * d.1 + d.2 * d.2;
+
+                if distance_squared < nearest {
+                    nearest = distance_squared;
+                    offset = d;
+                }
+            }
+        }
+    }
+
+    let distance = nearest.sqrt();
+    if distance == 0.0 {
+        return (0.0, (0.0, 0.0, 0.0));
+    }
+
+    (
+        distance,
+        (
+            offset.0 / distance,
+            offset.1 / distance,
+            offset.2 / distance,
+        ),
+    )
+}
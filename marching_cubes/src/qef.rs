@@ -0,0 +1,175 @@
+use crate::vector::{add, dot, scale, sub, Vec3};
+
+/// Quadratic error function of a set of planes through points with normals,
+/// whose minimizer is the point closest to all of them.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Qef {
+    /// `AᵀA` and `Aᵀb` of the least squares system `nᵢ · x = nᵢ · pᵢ`
+    normal_matrix: [[f32; 3]; 3],
+    normal_rhs: Vec3,
+    point_sum: Vec3,
+    count: usize,
+}
+
+impl Qef {
+    pub fn add(&mut self, point: Vec3, normal: Vec3) {
+        let n = [normal.0, normal.1, normal.2];
+        for (row, &ni) in self.normal_matrix.iter_mut().zip(&n) {
+            for (entry, &nj) in row.iter_mut().zip(&n) {
+                *entry += ni * nj;
+            }
+        }
+        self.normal_rhs = add(self.normal_rhs, scale(normal, dot(normal, point)));
+        self.point_sum = add(self.point_sum, point);
+        self.count += 1;
+    }
+
+    /// The average of the points, where the planes pin nothing down.
+    pub fn mass_point(&self) -> Vec3 {
+        scale(self.point_sum, 1.0 / self.count.max(1) as f32)
+    }
+
+    /// The minimizer closest to the mass point.
+    ///
+    /// Directions whose singular value is below `singular_value_threshold`
+    /// times the largest are treated as free and left at the mass point, so
+    /// nearly parallel planes can't fling the solution far away.
+    pub fn solve(&self, singular_value_threshold: f32) -> Vec3 {
+        self.solve_keeping(|_, value, largest| {
+            value.sqrt() >= singular_value_threshold * largest.sqrt()
+        })
+    }
+
+    /// [`Qef::solve`] keeping only the `rank` largest singular values, 2 for
+    /// planes known to meet along a crease and 3 for a corner.
+    pub fn solve_rank(&self, rank: usize) -> Vec3 {
+        self.solve_keeping(|order, _, _| order < rank)
+    }
+
+    /// `keep` gets the position of each eigenvalue of `AᵀA`, a squared
+    /// singular value, in descending order, the eigenvalue and the largest
+    /// one.
+    fn solve_keeping(&self, keep: impl Fn(usize, f32, f32) -> bool) -> Vec3 {
+        let mass_point = self.mass_point();
+        let m = self.normal_matrix;
+        let row = |i: usize| (m[i][0], m[i][1], m[i][2]);
+        // Solve relative to the mass point
+        let rhs = sub(
+            self.normal_rhs,
+            (
+                dot(row(0), mass_point),
+                dot(row(1), mass_point),
+                dot(row(2), mass_point),
+            ),
+        );
+
+        // The eigenvalues of the symmetric normal matrix are the squared
+        // singular values of A
+        let (values, vectors) = eigen(m);
+        let largest = values
+            .iter()
+            .fold(0.0f32, |largest, &value| largest.max(value));
+        if largest <= f32::EPSILON {
+            return mass_point;
+        }
+
+        let mut offset = (0.0, 0.0, 0.0);
+        for (k, &value) in values.iter().enumerate() {
+            let order = values
+                .iter()
+                .enumerate()
+                .filter(|&(j, &other)| other > value || (other == value && j < k))
+                .count();
+            if !keep(order, value, largest) || value <= f32::EPSILON * largest {
+                continue;
+            }
+            let vector = (vectors[0][k], vectors[1][k], vectors[2][k]);
+            offset = add(offset, scale(vector, dot(vector, rhs) / value));
+        }
+
+        add(mass_point, offset)
+    }
+}
+
+/// Eigenvalues and eigenvectors, as columns, of a symmetric 3x3 matrix by
+/// Jacobi rotations.
+fn eigen(mut a: [[f32; 3]; 3]) -> ([f32; 3], [[f32; 3]; 3]) {
+    let mut vectors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    for _ in 0..8 {
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q].abs() <= f32::EPSILON * (a[p][p].abs() + a[q][q].abs()) {
+                a[p][q] = 0.0;
+                a[q][p] = 0.0;
+                continue;
+            }
+
+            // The rotation that zeroes a[p][q]
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+
+            // a = JᵀaJ and vectors = vectors J, first the columns
+            for row in a.iter_mut().chain(&mut vectors) {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            for (k, (pk, qk)) in row_p.into_iter().zip(row_q).enumerate() {
+                a[p][k] = c * pk - s * qk;
+                a[q][k] = s * pk + c * qk;
+            }
+        }
+    }
+
+    ([a[0][0], a[1][1], a[2][2]], vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(a: Vec3, b: Vec3) -> f32 {
+        let offset = sub(a, b);
+        dot(offset, offset).sqrt()
+    }
+
+    #[test]
+    fn corner_of_three_planes() {
+        let mut qef = Qef::default();
+        qef.add((0.5, 2.0, 3.0), (1.0, 0.0, 0.0));
+        qef.add((1.0, -1.0, 3.0), (0.0, 1.0, 0.0));
+        qef.add((1.0, 2.0, 0.0), (0.0, 0.0, -1.0));
+        qef.add((0.5, -1.0, 7.0), (0.6, 0.8, 0.0));
+
+        assert!(distance(qef.solve(0.1), (0.5, -1.0, 0.0)) < 1e-4);
+        assert!(distance(qef.solve_rank(3), (0.5, -1.0, 0.0)) < 1e-4);
+    }
+
+    #[test]
+    fn crease_leaves_its_direction_at_the_mass_point() {
+        let mut qef = Qef::default();
+        qef.add((0.0, 0.0, 1.0), (1.0, 0.0, 0.0));
+        qef.add((-1.0, 0.0, 3.0), (0.0, 1.0, 0.0));
+
+        // Along z the planes pin nothing down
+        assert!(distance(qef.solve_rank(2), (0.0, 0.0, 2.0)) < 1e-4);
+        assert!(distance(qef.solve(0.1), (0.0, 0.0, 2.0)) < 1e-4);
+    }
+
+    #[test]
+    fn threshold_compares_singular_values() {
+        // Two planes whose normals are so close that the singular values of
+        // their system are 0.2 apart as a ratio, meeting along x = 0, y = 1
+        let angle = 2.0 * 0.2f32.atan();
+        let (c, s) = (angle.cos(), angle.sin());
+        let mut qef = Qef::default();
+        qef.add((0.0, 0.0, 0.0), (1.0, 0.0, 0.0));
+        qef.add((s, 1.0 - c, 0.0), (c, s, 0.0));
+
+        assert!(distance(qef.solve(0.1), (0.0, 1.0, 0.0)) < 1e-3);
+        assert!(distance(qef.solve(0.3), (0.0, 1.0, 0.0)) > 0.1);
+    }
+}
@@ -0,0 +1,550 @@
+use crate::field::{Bounds, ScalarField};
+use crate::vector::{closest_on_segment, dot, length, scale, sub, Vec3};
+
+/// Signed distance function primitives.
+///
+/// Distances are negative inside. As a [`ScalarField`] the sign is flipped,
+/// so the surface is the `0.0` isosurface and the inside is at or above it
+/// like for every other field. Round primitives are oriented along the y
+/// axis; rotate them with a transform modifier.
+///
+/// Formulas follow Inigo Quilez's distance function collection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sdf {
+    Sphere {
+        center: (f32, f32, f32),
+        radius: f32,
+    },
+    /// Axis-aligned box with the given half extents.
+    Box {
+        center: (f32, f32, f32),
+        half_extents: (f32, f32, f32),
+    },
+    /// Box whose edges are rounded off by `rounding`, within the same extents.
+    RoundBox {
+        center: (f32, f32, f32),
+        half_extents: (f32, f32, f32),
+        rounding: f32,
+    },
+    /// Torus lying in the xz plane.
+    Torus {
+        center: (f32, f32, f32),
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    Capsule {
+        a: (f32, f32, f32),
+        b: (f32, f32, f32),
+        radius: f32,
+    },
+    /// Capped cylinder along y.
+    Cylinder {
+        center: (f32, f32, f32),
+        radius: f32,
+        half_height: f32,
+    },
+    /// Capped cone along y, going from `bottom_radius` to `top_radius`.
+    /// A `top_radius` of zero gives a regular cone.
+    Cone {
+        center: (f32, f32, f32),
+        half_height: f32,
+        bottom_radius: f32,
+        top_radius: f32,
+    },
+    /// Half-space below the plane `dot(p, normal) + offset = 0`, with a unit
+    /// `normal` pointing out of the solid.
+    Plane {
+        normal: (f32, f32, f32),
+        offset: f32,
+    },
+    /// Axis-aligned ellipsoid. The distance is a bound, not exact.
+    Ellipsoid {
+        center: (f32, f32, f32),
+        radii: (f32, f32, f32),
+    },
+    /// Hexagonal prism along y, `radius` being the distance from the axis to
+    /// the side faces.
+    HexPrism {
+        center: (f32, f32, f32),
+        radius: f32,
+        half_height: f32,
+    },
+}
+
+/// Distance of a 2D box with half extents `(0, 0)` given the per-axis
+/// distances `q`, and its gradient.
+fn box_2d(q: (f32, f32)) -> (f32, (f32, f32)) {
+    let outside = (q.0.max(0.0), q.1.max(0.0));
+    let outside_length = (outside.0 * outside.0 + outside.1 * outside.1).sqrt();
+
+    if outside_length > 0.0 {
+        (
+            outside_length,
+            (outside.0 / outside_length, outside.1 / outside_length),
+        )
+    } else if q.0 > q.1 {
+        (q.0, (1.0, 0.0))
+    } else {
+        (q.1, (0.0, 1.0))
+    }
+}
+
+/// Distance of an origin-centered box given `abs(p) - half_extents`, and its
+/// gradient with respect to `abs(p)`.
+fn box_3d(q: Vec3) -> (f32, Vec3) {
+    let outside = (q.0.max(0.0), q.1.max(0.0), q.2.max(0.0));
+    let outside_length = length(outside);
+
+    if outside_length > 0.0 {
+        (outside_length, scale(outside, 1.0 / outside_length))
+    } else if q.0 >= q.1 && q.0 >= q.2 {
+        (q.0, (1.0, 0.0, 0.0))
+    } else if q.1 >= q.2 {
+        (q.1, (0.0, 1.0, 0.0))
+    } else {
+        (q.2, (0.0, 0.0, 1.0))
+    }
+}
+
+/// Applies the signs of `p` to a gradient computed for `abs(p)`.
+fn mirror(gradient: Vec3, p: Vec3) -> Vec3 {
+    (
+        gradient.0.copysign(p.0),
+        gradient.1.copysign(p.1),
+        gradient.2.copysign(p.2),
+    )
+}
+
+/// Normalizes, leaving zero vectors alone.
+fn direction(v: Vec3) -> Vec3 {
+    let l = length(v);
+    if l > 0.0 {
+        scale(v, 1.0 / l)
+    } else {
+        (0.0, 0.0, 0.0)
+    }
+}
+
+impl Sdf {
+    pub fn sphere(center: (f32, f32, f32), radius: f32) -> Sdf {
+        Sdf::Sphere { center, radius }
+    }
+
+    pub fn cuboid(center: (f32, f32, f32), half_extents: (f32, f32, f32)) -> Sdf {
+        Sdf::Box {
+            center,
+            half_extents,
+        }
+    }
+
+    pub fn round_box(center: (f32, f32, f32), half_extents: (f32, f32, f32), rounding: f32) -> Sdf {
+        Sdf::RoundBox {
+            center,
+            half_extents,
+            rounding,
+        }
+    }
+
+    pub fn torus(center: (f32, f32, f32), major_radius: f32, minor_radius: f32) -> Sdf {
+        Sdf::Torus {
+            center,
+            major_radius,
+            minor_radius,
+        }
+    }
+
+    pub fn capsule(a: (f32, f32, f32), b: (f32, f32, f32), radius: f32) -> Sdf {
+        Sdf::Capsule { a, b, radius }
+    }
+
+    pub fn cylinder(center: (f32, f32, f32), radius: f32, half_height: f32) -> Sdf {
+        Sdf::Cylinder {
+            center,
+            radius,
+            half_height,
+        }
+    }
+
+    pub fn cone(
+        center: (f32, f32, f32),
+        half_height: f32,
+        bottom_radius: f32,
+        top_radius: f32,
+    ) -> Sdf {
+        Sdf::Cone {
+            center,
+            half_height,
+            bottom_radius,
+            top_radius,
+        }
+    }
+
+    /// `normal` doesn't need to be normalized.
+    pub fn plane(normal: (f32, f32, f32), offset: f32) -> Sdf {
+        let length = length(normal);
+        Sdf::Plane {
+            normal: scale(normal, 1.0 / length),
+            offset: offset / length,
+        }
+    }
+
+    pub fn ellipsoid(center: (f32, f32, f32), radii: (f32, f32, f32)) -> Sdf {
+        Sdf::Ellipsoid { center, radii }
+    }
+
+    pub fn hex_prism(center: (f32, f32, f32), radius: f32, half_height: f32) -> Sdf {
+        Sdf::HexPrism {
+            center,
+            radius,
+            half_height,
+        }
+    }
+
+    /// Signed distance from a point to the surface, negative inside.
+    pub fn distance(&self, x: f32, y: f32, z: f32) -> f32 {
+        let p = (x, y, z);
+
+        match *self {
+            Sdf::Sphere { center, radius } => length(sub(p, center)) - radius,
+            Sdf::Box {
+                center,
+                half_extents,
+            } => {
+                let p = sub(p, center);
+                box_3d(sub((p.0.abs(), p.1.abs(), p.2.abs()), half_extents)).0
+            }
+            Sdf::RoundBox {
+                center,
+                half_extents,
+                rounding,
+            } => {
+                let p = sub(p, center);
+                let inner = sub(half_extents, (rounding, rounding, rounding));
+                box_3d(sub((p.0.abs(), p.1.abs(), p.2.abs()), inner)).0 - rounding
+            }
+            Sdf::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let p = sub(p, center);
+                let q = ((p.0 * p.0 + p.2 * p.2).sqrt() - major_radius, p.1);
+                (q.0 * q.0 + q.1 * q.1).sqrt() - minor_radius
+            }
+            Sdf::Capsule { a, b, radius } => length(sub(p, closest_on_segment(p, a, b))) - radius,
+            Sdf::Cylinder {
+                center,
+                radius,
+                half_height,
+            } => {
+                let p = sub(p, center);
+                let radial = (p.0 * p.0 + p.2 * p.2).sqrt();
+                box_2d((radial - radius, p.1.abs() - half_height)).0
+            }
+            Sdf::Cone {
+                center,
+                half_height,
+                bottom_radius,
+                top_radius,
+            } => {
+                let p = sub(p, center);
+                let q = ((p.0 * p.0 + p.2 * p.2).sqrt(), p.1);
+                let k1 = (top_radius, half_height);
+                let k2 = (top_radius - bottom_radius, 2.0 * half_height);
+
+                let cap_radius = if q.1 < 0.0 { bottom_radius } else { top_radius };
+                let ca = (q.0 - q.0.min(cap_radius), q.1.abs() - half_height);
+                let t = (((k1.0 - q.0) * k2.0 + (k1.1 - q.1) * k2.1) / (k2.0 * k2.0 + k2.1 * k2.1))
+                    .clamp(0.0, 1.0);
+                let cb = (q.0 - k1.0 + k2.0 * t, q.1 - k1.1 + k2.1 * t);
+
+                let sign = if cb.0 < 0.0 && ca.1 < 0.0 { -1.0 } else { 1.0 };
+                sign * (ca.0 * ca.0 + ca.1 * ca.1)
+                    .min(cb.0 * cb.0 + cb.1 * cb.1)
+                    .sqrt()
+            }
+            Sdf::Plane { normal, offset } => dot(p, normal) + offset,
+            Sdf::Ellipsoid { center, radii } => {
+                let p = sub(p, center);
+                let k0 = length((p.0 / radii.0, p.1 / radii.1, p.2 / radii.2));
+                let k1 = length((
+                    p.0 / (radii.0 * radii.0),
+                    p.1 / (radii.1 * radii.1),
+                    p.2 / (radii.2 * radii.2),
+                ));
+                if k1 == 0.0 {
+                    // At the very center
+                    return -radii.0.min(radii.1).min(radii.2);
+                }
+
+                k0 * (k0 - 1.0) / k1
+            }
+            Sdf::HexPrism {
+                center,
+                radius,
+                half_height,
+            } => {
+                const K: Vec3 = (-0.866_025_4, 0.5, 0.577_350_26);
+
+                // The hexagon lies in the xz plane
+                let p = sub(p, center);
+                let (mut hx, mut hy) = (p.0.abs(), p.2.abs());
+                let fold = 2.0 * (K.0 * hx + K.1 * hy).min(0.0);
+                hx -= fold * K.0;
+                hy -= fold * K.1;
+
+                let edge_x = hx.clamp(-K.2 * radius, K.2 * radius);
+                let side =
+                    ((hx - edge_x).powi(2) + (hy - radius).powi(2)).sqrt() * (hy - radius).signum();
+                box_2d((side, p.1.abs() - half_height)).0
+            }
+        }
+    }
+
+    /// Gradient of the distance, in closed form for every primitive.
+    pub fn distance_gradient(&self, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+        let p = (x, y, z);
+
+        match *self {
+            Sdf::Sphere { center, .. } => direction(sub(p, center)),
+            Sdf::Box {
+                center,
+                half_extents,
+            }
+            | Sdf::RoundBox {
+                center,
+                half_extents,
+                ..
+            } => {
+                let rounding = match *self {
+                    Sdf::RoundBox { rounding, .. } => rounding,
+                    _ => 0.0,
+                };
+                let p = sub(p, center);
+                let inner = sub(half_extents, (rounding, rounding, rounding));
+                let (_, gradient) = box_3d(sub((p.0.abs(), p.1.abs(), p.2.abs()), inner));
+                mirror(gradient, p)
+            }
+            Sdf::Torus {
+                center,
+                major_radius,
+                ..
+            } => {
+                let p = sub(p, center);
+                let radial = (p.0 * p.0 + p.2 * p.2).sqrt();
+                let (qx, qy) = (radial - major_radius, p.1);
+                let q_length = (qx * qx + qy * qy).sqrt();
+                if q_length == 0.0 || radial == 0.0 {
+                    return (0.0, 0.0, 0.0);
+                }
+
+                let (dx, dy) = (qx / q_length, qy / q_length);
+                (dx * p.0 / radial, dy, dx * p.2 / radial)
+            }
+            Sdf::Capsule { a, b, .. } => direction(sub(p, closest_on_segment(p, a, b))),
+            Sdf::Cylinder {
+                center,
+                radius,
+                half_height,
+            } => {
+                let p = sub(p, center);
+                let radial = (p.0 * p.0 + p.2 * p.2).sqrt();
+                let (_, (dr, dy)) = box_2d((radial - radius, p.1.abs() - half_height));
+                let (ux, uz) = if radial > 0.0 {
+                    (p.0 / radial, p.2 / radial)
+                } else {
+                    (0.0, 0.0)
+                };
+                (dr * ux, dy.copysign(p.1), dr * uz)
+            }
+            Sdf::Cone {
+                center,
+                half_height,
+                bottom_radius,
+                top_radius,
+            } => {
+                let p = sub(p, center);
+                let radial = (p.0 * p.0 + p.2 * p.2).sqrt();
+                let q = (radial, p.1);
+                let k1 = (top_radius, half_height);
+                let k2 = (top_radius - bottom_radius, 2.0 * half_height);
+
+                let cap_radius = if q.1 < 0.0 { bottom_radius } else { top_radius };
+                let ca = (q.0 - q.0.min(cap_radius), q.1.abs() - half_height);
+                let t = (((k1.0 - q.0) * k2.0 + (k1.1 - q.1) * k2.1) / (k2.0 * k2.0 + k2.1 * k2.1))
+                    .clamp(0.0, 1.0);
+                let cb = (q.0 - k1.0 + k2.0 * t, q.1 - k1.1 + k2.1 * t);
+
+                // Both offsets move one for one with q, the side's along the
+                // slant too as it stays perpendicular to it
+                let (offset, flip) = if ca.0 * ca.0 + ca.1 * ca.1 < cb.0 * cb.0 + cb.1 * cb.1 {
+                    (ca, 1.0f32.copysign(q.1))
+                } else {
+                    (cb, 1.0)
+                };
+                let offset_length = (offset.0 * offset.0 + offset.1 * offset.1).sqrt();
+                if offset_length == 0.0 {
+                    return (0.0, 0.0, 0.0);
+                }
+
+                let sign = if cb.0 < 0.0 && ca.1 < 0.0 { -1.0 } else { 1.0 };
+                let (dr, dy) = (
+                    sign * offset.0 / offset_length,
+                    sign * flip * offset.1 / offset_length,
+                );
+                let (ux, uz) = if radial > 0.0 {
+                    (p.0 / radial, p.2 / radial)
+                } else {
+                    (0.0, 0.0)
+                };
+                (dr * ux, dy, dr * uz)
+            }
+            Sdf::Plane { normal, .. } => normal,
+            Sdf::Ellipsoid { center, radii } => {
+                let p = sub(p, center);
+                let squared = (radii.0 * radii.0, radii.1 * radii.1, radii.2 * radii.2);
+                let k0 = length((p.0 / radii.0, p.1 / radii.1, p.2 / radii.2));
+                let k1 = length((p.0 / squared.0, p.1 / squared.1, p.2 / squared.2));
+                if k1 == 0.0 {
+                    return (0.0, 0.0, 0.0);
+                }
+
+                // Quotient rule on k0 (k0 - 1) / k1
+                let dk0 = (
+                    p.0 / (squared.0 * k0),
+                    p.1 / (squared.1 * k0),
+                    p.2 / (squared.2 * k0),
+                );
+                let dk1 = (
+                    p.0 / (squared.0 * squared.0 * k1),
+                    p.1 / (squared.1 * squared.1 * k1),
+                    p.2 / (squared.2 * squared.2 * k1),
+                );
+                let (a, b) = ((2.0 * k0 - 1.0) / k1, k0 * (k0 - 1.0) / (k1 * k1));
+                (
+                    a * dk0.0 - b * dk1.0,
+                    a * dk0.1 - b * dk1.1,
+                    a * dk0.2 - b * dk1.2,
+                )
+            }
+            Sdf::HexPrism {
+                center,
+                radius,
+                half_height,
+            } => {
+                const K: Vec3 = (-0.866_025_4, 0.5, 0.577_350_26);
+
+                let p = sub(p, center);
+                let (mut hx, mut hy) = (p.0.abs(), p.2.abs());
+                let folded = K.0 * hx + K.1 * hy < 0.0;
+                if folded {
+                    let fold = 2.0 * (K.0 * hx + K.1 * hy);
+                    hx -= fold * K.0;
+                    hy -= fold * K.1;
+                }
+
+                // Gradient of the side distance in the folded frame
+                let edge_x = hx.clamp(-K.2 * radius, K.2 * radius);
+                let (side, (mut gx, mut gy)) = if hx == edge_x {
+                    (hy - radius, (0.0, 1.0))
+                } else {
+                    let offset = (hx - edge_x, hy - radius);
+                    let offset_length = (offset.0 * offset.0 + offset.1 * offset.1).sqrt();
+                    let sign = (hy - radius).signum();
+                    (
+                        offset_length * sign,
+                        (
+                            sign * offset.0 / offset_length,
+                            sign * offset.1 / offset_length,
+                        ),
+                    )
+                };
+                let (_, (ds, dy)) = box_2d((side, p.1.abs() - half_height));
+                gx *= ds;
+                gy *= ds;
+
+                // The fold is a reflection, its own transpose
+                if folded {
+                    let along = 2.0 * (K.0 * gx + K.1 * gy);
+                    gx -= along * K.0;
+                    gy -= along * K.1;
+                }
+                mirror((gx, dy, gy), p)
+            }
+        }
+    }
+}
+
+impl ScalarField for Sdf {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        -self.distance(x, y, z)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let (dx, dy, dz) = self.distance_gradient(x, y, z);
+        Some((-dx, -dy, -dz))
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let around = |center: Vec3, extent: Vec3| {
+            Some(Bounds::new(
+                sub(center, extent),
+                (
+                    center.0 + extent.0,
+                    center.1 + extent.1,
+                    center.2 + extent.2,
+                ),
+            ))
+        };
+
+        match *self {
+            Sdf::Sphere { center, radius } => around(center, (radius, radius, radius)),
+            Sdf::Box {
+                center,
+                half_extents,
+            }
+            | Sdf::RoundBox {
+                center,
+                half_extents,
+                ..
+            } => around(center, half_extents),
+            Sdf::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let reach = major_radius + minor_radius;
+                around(center, (reach, minor_radius, reach))
+            }
+            Sdf::Capsule { a, b, radius } => {
+                let r = (radius, radius, radius);
+                Some(around(a, r)?.union(&around(b, r)?))
+            }
+            Sdf::Cylinder {
+                center,
+                radius,
+                half_height,
+            } => around(center, (radius, half_height, radius)),
+            Sdf::Cone {
+                center,
+                half_height,
+                bottom_radius,
+                top_radius,
+            } => {
+                let radius = bottom_radius.max(top_radius);
+                around(center, (radius, half_height, radius))
+            }
+            Sdf::Plane { .. } => None,
+            Sdf::Ellipsoid { center, radii } => around(center, radii),
+            Sdf::HexPrism {
+                center,
+                radius,
+                half_height,
+            } => {
+                // Corners are further out than the side faces
+                let corner = radius / 0.866_025_4;
+                around(center, (corner, half_height, corner))
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::field::ScalarField;
+use crate::grid::Samples;
+use crate::lookup_tables::EDGE_CONNECTIONS;
+use crate::marching::{Lattice, Point, CORNER_OFFSETS, NO_VERTEX};
+use crate::vector::{add, dot, length, scale, sub, Vec3};
+use crate::GridData;
+
+/// Where the surface crosses a cell edge.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Crossing {
+    /// The edge's lattice points.
+    pub edge: (Point, Point),
+    /// Fraction of the way along the edge.
+    pub t: f32,
+    /// The crossing in padded lattice coordinates.
+    pub point: Vec3,
+    /// The normal for edges into the padding.
+    pub cap: Option<Vec3>,
+}
+
+/// Runs naive surface nets over `samples` of `field`.
+///
+/// Every cell the surface passes through gets a single vertex at the average
+/// of its edge crossings. There are fewer vertices than with marching cubes
+/// and hardly any slivers.
+pub(crate) fn surface_nets<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    closed: bool,
+) -> GridData {
+    dual_mesh(
+        samples,
+        field,
+        threshold,
+        closed,
+        |lattice, _, crossings| {
+            let sum = crossings
+                .iter()
+                .fold((0.0, 0.0, 0.0), |sum, crossing| add(sum, crossing.point));
+            lattice.position(scale(sum, 1.0 / crossings.len() as f32))
+        },
+    )
+}
+
+/// Meshes the dual of the lattice: every cell the surface passes through gets
+/// a single vertex, put wherever `place` says from the cell's lower corner
+/// and its edge crossings, and every crossed lattice edge becomes a quad
+/// joining the vertices of the four cells around it.
+pub(crate) fn dual_mesh<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    closed: bool,
+    mut place: impl FnMut(&Lattice<F>, Point, &[Crossing]) -> Vec3,
+) -> GridData {
+    let mut lattice = Lattice::new(samples, field, threshold, closed);
+    let (nx, ny, nz) = lattice.cells;
+    let cell_index = |(x, y, z): Point| x + nx * (y + ny * z);
+    let mut cell_vertices = vec![NO_VERTEX; nx * ny * nz];
+
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..nx {
+                let corners = CORNER_OFFSETS.map(|(dx, dy, dz)| (x + dx, y + dy, z + dz));
+                let inside = corners.map(|corner| lattice.inside(corner));
+                if inside.iter().all(|&flag| flag == inside[0]) {
+                    continue;
+                }
+
+                let mut crossings = Vec::with_capacity(12);
+                for &(i, j) in &EDGE_CONNECTIONS {
+                    if inside[i] == inside[j] {
+                        continue;
+                    }
+
+                    let (a, b) = (corners[i], corners[j]);
+                    let (t, cap) = lattice.crossing(a, b);
+                    let point = (
+                        a.0 as f32 + t * (b.0 as f32 - a.0 as f32),
+                        a.1 as f32 + t * (b.1 as f32 - a.1 as f32),
+                        a.2 as f32 + t * (b.2 as f32 - a.2 as f32),
+                    );
+                    crossings.push(Crossing {
+                        edge: (a, b),
+                        t,
+                        point,
+                        cap,
+                    });
+                }
+
+                let position = place(&lattice, (x, y, z), &crossings);
+                // Cells touching the domain boundary in closed mode get the
+                // cap normals where all their crossings are capped
+                let normal = crossings
+                    .iter()
+                    .try_fold((0.0, 0.0, 0.0), |sum, crossing| {
+                        Some(add(sum, crossing.cap?))
+                    })
+                    .filter(|&caps| length(caps) > f32::EPSILON)
+                    .map(|caps| scale(caps, 1.0 / length(caps)));
+                cell_vertices[cell_index((x, y, z))] = lattice.push_vertex(position, normal);
+            }
+        }
+    }
+
+    // Quads across the crossed lattice edges that have four cells around
+    // them, which in closed mode is all of them
+    for z in 0..=nz {
+        for y in 0..=ny {
+            for x in 0..=nx {
+                let point = [x, y, z];
+                for axis in 0..3 {
+                    // The other two axes, in the order that makes the quad
+                    // counter-clockwise around the positive edge direction
+                    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                    let limits = [nx, ny, nz];
+                    if point[axis] >= limits[axis]
+                        || point[u] == 0
+                        || point[u] >= limits[u]
+                        || point[v] == 0
+                        || point[v] >= limits[v]
+                    {
+                        continue;
+                    }
+
+                    let mut upper = point;
+                    upper[axis] += 1;
+                    let lower_inside = lattice.inside((x, y, z));
+                    if lower_inside == lattice.inside((upper[0], upper[1], upper[2])) {
+                        continue;
+                    }
+
+                    let cell = |du: usize, dv: usize| {
+                        let mut cell = point;
+                        cell[u] -= 1 - du;
+                        cell[v] -= 1 - dv;
+                        cell_vertices[cell_index((cell[0], cell[1], cell[2]))]
+                    };
+                    let mut quad = [cell(0, 0), cell(1, 0), cell(1, 1), cell(0, 1)];
+                    // Facing away from the inside, counter-clockwise seen
+                    // from outside
+                    if !lower_inside {
+                        quad.reverse();
+                    }
+                    emit_quad(&mut lattice.mesh, quad);
+                }
+            }
+        }
+    }
+
+    lattice.mesh
+}
+
+/// Adds a quad as two triangles, split along its shorter diagonal.
+fn emit_quad(mesh: &mut GridData, [a, b, c, d]: [u32; 4]) {
+    let position = |vertex: u32| {
+        let i = vertex as usize * 3;
+        (mesh.vertices[i], mesh.vertices[i + 1], mesh.vertices[i + 2])
+    };
+    let distance = |i: u32, j: u32| {
+        let offset = sub(position(i), position(j));
+        dot(offset, offset)
+    };
+
+    let triangles = if distance(a, c) <= distance(b, d) {
+        [a, b, c, a, c, d]
+    } else {
+        [b, c, d, b, d, a]
+    };
+    mesh.indices.extend_from_slice(&triangles);
+}
@@ -0,0 +1,384 @@
+use crate::field::{Bounds, ScalarField};
+use crate::formats::{Endian, VolumeError};
+use crate::noise::Noise;
+
+/// How a [`Heightmap`] is sampled between its points.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Interpolation {
+    /// Cheap, but the slope jumps at every sample, which shows up as
+    /// creases in the normals.
+    #[default]
+    Bilinear,
+    /// Catmull-Rom, smooth slopes through the samples.
+    Bicubic,
+}
+
+/// Grid of heights, stored row by row with x fastest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heightmap {
+    pub columns: usize,
+    pub rows: usize,
+    pub heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// # Panics
+    ///
+    /// Panics if `heights` doesn't hold exactly `columns * rows` values.
+    pub fn new(columns: usize, rows: usize, heights: Vec<f32>) -> Heightmap {
+        assert_eq!(
+            heights.len(),
+            columns * rows,
+            "expected one height per column and row"
+        );
+
+        Heightmap {
+            columns,
+            rows,
+            heights,
+        }
+    }
+
+    /// [`Heightmap::new`], returning an error instead of panicking when
+    /// `heights` doesn't hold exactly `columns * rows` values.
+    pub fn try_new(
+        columns: usize,
+        rows: usize,
+        heights: Vec<f32>,
+    ) -> Result<Heightmap, VolumeError> {
+        let count = sample_count(columns, rows)?;
+        if heights.len() != count {
+            return Err(VolumeError::Format(format!(
+                "expected {columns}x{rows} heights, found {} values",
+                heights.len()
+            )));
+        }
+
+        Ok(Heightmap {
+            columns,
+            rows,
+            heights,
+        })
+    }
+
+    /// Reads a binary (`P5`) or ASCII (`P2`) PGM image, 8 or 16 bits, with
+    /// heights normalized to `[0, 1]`.
+    pub fn from_pgm(bytes: &[u8]) -> Result<Heightmap, VolumeError> {
+        // Magic, width, height and maximum value, each possibly followed by
+        // comments
+        let mut fields = Vec::with_capacity(4);
+        let mut offset = 0;
+        while fields.len() < 4 {
+            match bytes.get(offset) {
+                None => return Err(VolumeError::Format("truncated PGM header".to_string())),
+                Some(b'#') => {
+                    while bytes.get(offset).is_some_and(|&byte| byte != b'\n') {
+                        offset += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => offset += 1,
+                Some(_) => {
+                    let start = offset;
+                    while bytes
+                        .get(offset)
+                        .is_some_and(|byte| !byte.is_ascii_whitespace())
+                    {
+                        offset += 1;
+                    }
+                    fields.push(String::from_utf8_lossy(&bytes[start..offset]).into_owned());
+                }
+            }
+        }
+
+        let number = |field: &str| {
+            field
+                .parse::<usize>()
+                .map_err(|_| VolumeError::Format(format!("`{field}` is not a number")))
+        };
+        let (columns, rows, maximum) = (
+            number(&fields[1])?,
+            number(&fields[2])?,
+            number(&fields[3])?,
+        );
+        if maximum == 0 || maximum > u16::MAX as usize {
+            return Err(VolumeError::Format(format!("bad PGM maximum {maximum}")));
+        }
+        let count = sample_count(columns, rows)?;
+
+        let values = match fields[0].as_str() {
+            "P5" => {
+                // A single whitespace byte separates the header from the data
+                let data = bytes.get(offset + 1..).unwrap_or_default();
+                let size = if maximum > 255 { 2 } else { 1 };
+                let expected = count
+                    .checked_mul(size)
+                    .ok_or_else(|| VolumeError::Format("PGM size overflows".to_string()))?;
+                if data.len() < expected {
+                    return Err(VolumeError::Truncated {
+                        expected,
+                        found: data.len(),
+                    });
+                }
+
+                if size == 2 {
+                    data.chunks_exact(2)
+                        .take(count)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as f32)
+                        .collect()
+                } else {
+                    data[..count].iter().map(|&value| value as f32).collect()
+                }
+            }
+            "P2" => {
+                let text = String::from_utf8_lossy(&bytes[offset..]);
+                let values = text
+                    .split_ascii_whitespace()
+                    .take(count)
+                    .map(|token| number(token).map(|value| value as f32))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() < count {
+                    return Err(VolumeError::Format(format!(
+                        "expected {count} PGM values, found {}",
+                        values.len()
+                    )));
+                }
+                values
+            }
+            magic => return Err(VolumeError::Unsupported(format!("PGM type `{magic}`"))),
+        };
+
+        let maximum = maximum as f32;
+        Ok(Heightmap::new(
+            columns,
+            rows,
+            values.into_iter().map(|value| value / maximum).collect(),
+        ))
+    }
+
+    /// Reads headerless 16-bit heights, as exported by most terrain tools,
+    /// normalized to `[0, 1]`.
+    pub fn from_raw16(
+        bytes: &[u8],
+        columns: usize,
+        rows: usize,
+        endian: Endian,
+    ) -> Result<Heightmap, VolumeError> {
+        let count = sample_count(columns, rows)?;
+        let expected = count
+            .checked_mul(2)
+            .ok_or_else(|| VolumeError::Format("heightmap size overflows".to_string()))?;
+        if bytes.len() < expected {
+            return Err(VolumeError::Truncated {
+                expected,
+                found: bytes.len(),
+            });
+        }
+
+        let heights = bytes
+            .chunks_exact(2)
+            .take(count)
+            .map(|pair| {
+                let pair = [pair[0], pair[1]];
+                let value = match endian {
+                    Endian::Little => u16::from_le_bytes(pair),
+                    Endian::Big => u16::from_be_bytes(pair),
+                };
+                value as f32 / u16::MAX as f32
+            })
+            .collect();
+
+        Ok(Heightmap::new(columns, rows, heights))
+    }
+
+    /// Height at a column and row, clamped to the edges.
+    fn at(&self, column: isize, row: isize) -> f32 {
+        let column = column.clamp(0, self.columns as isize - 1) as usize;
+        let row = row.clamp(0, self.rows as isize - 1) as usize;
+        self.heights[column + self.columns * row]
+    }
+
+    /// Height at fractional sample coordinates, clamped to the edges, with
+    /// its slope along the columns and rows.
+    pub fn sample(&self, u: f32, v: f32, interpolation: Interpolation) -> (f32, (f32, f32)) {
+        if self.heights.is_empty() {
+            return (0.0, (0.0, 0.0));
+        }
+
+        let u = u.clamp(0.0, self.columns.saturating_sub(1) as f32);
+        let v = v.clamp(0.0, self.rows.saturating_sub(1) as f32);
+        let (column, row) = (u.floor(), v.floor());
+        let (tu, tv) = (u - column, v - row);
+        let (column, row) = (column as isize, row as isize);
+
+        // Weights of the neighbouring samples and their derivatives
+        let (weights_u, slopes_u, weights_v, slopes_v, first) = match interpolation {
+            Interpolation::Bilinear => (
+                [1.0 - tu, tu, 0.0, 0.0],
+                [-1.0, 1.0, 0.0, 0.0],
+                [1.0 - tv, tv, 0.0, 0.0],
+                [-1.0, 1.0, 0.0, 0.0],
+                0,
+            ),
+            Interpolation::Bicubic => {
+                let (weights_u, slopes_u) = catmull_rom(tu);
+                let (weights_v, slopes_v) = catmull_rom(tv);
+                (weights_u, slopes_u, weights_v, slopes_v, -1)
+            }
+        };
+
+        let mut height = 0.0;
+        let mut slope = (0.0, 0.0);
+        for j in 0..4 {
+            if weights_v[j] == 0.0 && slopes_v[j] == 0.0 {
+                continue;
+            }
+            for i in 0..4 {
+                if weights_u[i] == 0.0 && slopes_u[i] == 0.0 {
+                    continue;
+                }
+
+                let h = self.at(column + first + i as isize, row + first + j as isize);
+                height += weights_u[i] * weights_v[j] * h;
+                slope.0 += slopes_u[i] * weights_v[j] * h;
+                slope.1 += weights_u[i] * slopes_v[j] * h;
+            }
+        }
+
+        (height, slope)
+    }
+
+    fn range(&self) -> (f32, f32) {
+        self.heights
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), &height| {
+                (min.min(height), max.max(height))
+            })
+    }
+}
+
+/// Number of heights, guarding against headers with absurd sizes.
+fn sample_count(columns: usize, rows: usize) -> Result<usize, VolumeError> {
+    columns
+        .checked_mul(rows)
+        .ok_or_else(|| VolumeError::Format("heightmap size overflows".to_string()))
+}
+
+/// Catmull-Rom weights of the four samples around `t` and their derivatives.
+fn catmull_rom(t: f32) -> ([f32; 4], [f32; 4]) {
+    let (t2, t3) = (t * t, t * t * t);
+    (
+        [
+            0.5 * (-t3 + 2.0 * t2 - t),
+            0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+            0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+            0.5 * (t3 - t2),
+        ],
+        [
+            0.5 * (-3.0 * t2 + 4.0 * t - 1.0),
+            0.5 * (9.0 * t2 - 10.0 * t),
+            0.5 * (-9.0 * t2 + 8.0 * t + 1.0),
+            0.5 * (3.0 * t2 - 2.0 * t),
+        ],
+    )
+}
+
+/// Solid ground below a heightmap: positive underground and negative in the
+/// air, so it meshes at `0.0`.
+///
+/// The heightmap is stretched over `size` starting at `origin`, heights of
+/// `1.0` reaching `size.1` above it. Adding 3D `noise` to the field carves
+/// overhangs and caves a heightmap alone can't describe.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Terrain {
+    pub heightmap: Heightmap,
+    pub interpolation: Interpolation,
+    pub origin: (f32, f32, f32),
+    pub size: (f32, f32, f32),
+    pub noise: Option<Noise>,
+}
+
+impl Terrain {
+    pub fn new(heightmap: Heightmap, size: (f32, f32, f32)) -> Terrain {
+        Terrain {
+            heightmap,
+            interpolation: Interpolation::Bilinear,
+            origin: (0.0, 0.0, 0.0),
+            size,
+            noise: None,
+        }
+    }
+
+    /// World distance between heightmap samples along x and z.
+    fn spacing(&self) -> (f32, f32) {
+        let spacing = |size: f32, samples: usize| size / samples.saturating_sub(1).max(1) as f32;
+        (
+            spacing(self.size.0, self.heightmap.columns),
+            spacing(self.size.2, self.heightmap.rows),
+        )
+    }
+
+    fn ground(&self, x: f32, z: f32) -> (f32, (f32, f32)) {
+        let (sx, sz) = self.spacing();
+        let u = (x - self.origin.0) / sx;
+        let v = (z - self.origin.2) / sz;
+        let (height, (du, dv)) = self.heightmap.sample(u, v, self.interpolation);
+
+        (
+            self.origin.1 + height * self.size.1,
+            (du * self.size.1 / sx, dv * self.size.1 / sz),
+        )
+    }
+}
+
+impl ScalarField for Terrain {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        let noise = self.noise.map_or(0.0, |noise| noise.value(x, y, z));
+        self.ground(x, z).0 - y + noise
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let (_, (dx, dz)) = self.ground(x, z);
+        let (nx, ny, nz) = self
+            .noise
+            .map_or((0.0, 0.0, 0.0), |noise| noise.sample(x, y, z).1);
+
+        Some((dx + nx, -1.0 + ny, dz + nz))
+    }
+
+    /// The footprint of the heightmap, from a little below its lowest point
+    /// to above its highest, so a closed mesh comes out as a block of ground.
+    fn bounds(&self) -> Option<Bounds> {
+        let (low, high) = self.heightmap.range();
+        if low > high {
+            return None;
+        }
+
+        // Bicubic sampling overshoots, and noise moves the surface. The
+        // Catmull-Rom weights' absolute values sum to at most 1.25 per axis,
+        // so the 2D weights' to 1.5625: at most 0.5625 of the range lands
+        // past either end.
+        let overshoot = match self.interpolation {
+            Interpolation::Bilinear => 0.0,
+            Interpolation::Bicubic => 0.5625 * (high - low),
+        };
+        let noise = self.noise.map_or(0.0, |noise| {
+            (0..noise.octaves)
+                .map(|octave| (noise.amplitude * noise.gain.powi(octave as i32)).abs())
+                .sum()
+        });
+        let margin = (overshoot * self.size.1).abs() + noise + 0.05 * self.size.1.abs();
+        let (low, high) = (
+            self.origin.1 + low * self.size.1,
+            self.origin.1 + high * self.size.1,
+        );
+
+        Some(Bounds::new(
+            (self.origin.0, low.min(high) - margin, self.origin.2),
+            (
+                self.origin.0 + self.size.0,
+                low.max(high) + margin,
+                self.origin.2 + self.size.2,
+            ),
+        ))
+    }
+}
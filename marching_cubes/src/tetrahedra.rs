@@ -0,0 +1,145 @@
+use crate::field::ScalarField;
+use crate::grid::Samples;
+use crate::marching::{EdgeCache, Lattice, Point};
+use crate::vector::{add, cross, dot, scale, sub};
+use crate::GridData;
+
+/// The six tetrahedra around the cell diagonal from `(0, 0, 0)` to
+/// `(1, 1, 1)`, one per order of stepping along the axes.
+///
+/// Every cell is split the same way, so the diagonals on shared faces match
+/// up and neighbouring cells always agree on the surface crossing the face.
+/// The corners of each tetrahedron ascend along every axis.
+const TETRAHEDRA: [[Point; 4]; 6] = [
+    [(0, 0, 0), (1, 0, 0), (1, 1, 0), (1, 1, 1)],
+    [(0, 0, 0), (1, 0, 0), (1, 0, 1), (1, 1, 1)],
+    [(0, 0, 0), (0, 1, 0), (1, 1, 0), (1, 1, 1)],
+    [(0, 0, 0), (0, 1, 0), (0, 1, 1), (1, 1, 1)],
+    [(0, 0, 0), (0, 0, 1), (1, 0, 1), (1, 1, 1)],
+    [(0, 0, 0), (0, 0, 1), (0, 1, 1), (1, 1, 1)],
+];
+
+/// Runs marching tetrahedra over `samples` of `field`.
+///
+/// Each tetrahedron is cut by at most a single triangle or quad, so there
+/// are no ambiguous cases and no lookup tables, at the price of roughly
+/// twice the triangles of marching cubes.
+pub(crate) fn march_tetrahedra<F: ScalarField + ?Sized>(
+    samples: &Samples,
+    field: &F,
+    threshold: f32,
+    closed: bool,
+) -> GridData {
+    let mut lattice = Lattice::new(samples, field, threshold, closed);
+    let (nx, ny, nz) = lattice.cells;
+
+    // Edges step along one of the 7 directions with components 0 or 1
+    let mut edges = EdgeCache::new(lattice.cells, 7);
+
+    for z in 0..nz {
+        edges.start_layer(z);
+
+        for y in 0..ny {
+            for x in 0..nx {
+                let mut inside = [false; 8];
+                for (corner, flag) in inside.iter_mut().enumerate() {
+                    *flag = lattice.inside((
+                        x + (corner & 1),
+                        y + (corner >> 1 & 1),
+                        z + (corner >> 2),
+                    ));
+                }
+                if inside.iter().all(|&flag| flag == inside[0]) {
+                    continue;
+                }
+
+                for tetrahedron in &TETRAHEDRA {
+                    let corners = tetrahedron.map(|(dx, dy, dz)| (x + dx, y + dy, z + dz));
+                    let inside = tetrahedron.map(|(dx, dy, dz)| inside[dx | dy << 1 | dz << 2]);
+
+                    let mut vertex = |i: usize, j: usize| {
+                        let (lower, upper) = (corners[i.min(j)], corners[i.max(j)]);
+                        let direction = (upper.0 - lower.0)
+                            | (upper.1 - lower.1) << 1
+                            | (upper.2 - lower.2) << 2;
+                        edges.get_or_insert_with(lower, direction - 1, || {
+                            lattice.edge_vertex(lower, upper)
+                        })
+                    };
+
+                    // Fixed-size buffers, this being the innermost loop
+                    let (mut ins, mut outs) = ([0; 4], [0; 4]);
+                    let (mut in_count, mut out_count) = (0, 0);
+                    for (corner, &flag) in inside.iter().enumerate() {
+                        if flag {
+                            ins[in_count] = corner;
+                            in_count += 1;
+                        } else {
+                            outs[out_count] = corner;
+                            out_count += 1;
+                        }
+                    }
+                    let (ins, outs) = (&ins[..in_count], &outs[..out_count]);
+                    let point = |corner: usize| {
+                        let (x, y, z) = corners[corner];
+                        (x as f32, y as f32, z as f32)
+                    };
+                    // Midpoint of an edge, to orient triangles without
+                    // caring where exactly the crossings are
+                    let midpoint = |(i, j): (usize, usize)| scale(add(point(i), point(j)), 0.5);
+                    let centroid = |set: &[usize]| {
+                        let sum = set
+                            .iter()
+                            .fold((0.0, 0.0, 0.0), |sum, &corner| add(sum, point(corner)));
+                        scale(sum, 1.0 / set.len() as f32)
+                    };
+                    let outwards = sub(centroid(outs), centroid(ins));
+
+                    // The crossed edges in order around the cut
+                    let mut cut = [(0, 0); 4];
+                    let length = match (ins, outs) {
+                        (&[i], outs) => {
+                            for (edge, &o) in cut.iter_mut().zip(outs) {
+                                *edge = (i, o);
+                            }
+                            outs.len()
+                        }
+                        (ins, &[o]) => {
+                            for (edge, &i) in cut.iter_mut().zip(ins) {
+                                *edge = (i, o);
+                            }
+                            ins.len()
+                        }
+                        (&[i1, i2], &[o1, o2]) => {
+                            cut = [(i1, o1), (i1, o2), (i2, o2), (i2, o1)];
+                            4
+                        }
+                        _ => continue,
+                    };
+
+                    // Counter-clockwise seen from outside
+                    let (a, b, c) = (midpoint(cut[0]), midpoint(cut[1]), midpoint(cut[2]));
+                    let flip = dot(cross(sub(b, a), sub(c, a)), outwards) < 0.0;
+
+                    let mut indices = [0; 4];
+                    for (index, &(i, j)) in indices.iter_mut().zip(&cut[..length]) {
+                        *index = vertex(i, j);
+                    }
+                    let indices = &mut indices[..length];
+                    if flip {
+                        indices.reverse();
+                    }
+                    for k in 1..length - 1 {
+                        lattice.mesh.indices.extend_from_slice(&[
+                            indices[0],
+                            indices[k],
+                            indices[k + 1],
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+
+    lattice.mesh
+}
@@ -0,0 +1,100 @@
+//! Small helpers for the `(x, y, z)` tuples used throughout the crate.
+
+pub(crate) type Vec3 = (f32, f32, f32);
+
+#[inline]
+pub(crate) fn add(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+#[inline]
+pub(crate) fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+#[inline]
+pub(crate) fn scale(a: Vec3, s: f32) -> Vec3 {
+    (a.0 * s, a.1 * s, a.2 * s)
+}
+
+#[inline]
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f32 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+#[inline]
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    (
+        a.1 * b.2 - a.2 * b.1,
+        a.2 * b.0 - a.0 * b.2,
+        a.0 * b.1 - a.1 * b.0,
+    )
+}
+
+#[inline]
+pub(crate) fn length(a: Vec3) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// Closest point to `p` on the segment `a`-`b`.
+pub(crate) fn closest_on_segment(p: Vec3, a: Vec3, b: Vec3) -> Vec3 {
+    let ab = sub(b, a);
+    let length_squared = dot(ab, ab);
+    if length_squared <= f32::EPSILON {
+        return a;
+    }
+
+    let t = (dot(sub(p, a), ab) / length_squared).clamp(0.0, 1.0);
+    add(a, scale(ab, t))
+}
+
+/// Closest point to `p` on the triangle `a`-`b`-`c`, following the region
+/// tests from Ericson's "Real-Time Collision Detection".
+pub(crate) fn closest_on_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let ab = sub(b, a);
+    let ac = sub(c, a);
+
+    let ap = sub(p, a);
+    let d1 = dot(ab, ap);
+    let d2 = dot(ac, ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = sub(p, b);
+    let d3 = dot(ab, bp);
+    let d4 = dot(ac, bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return add(a, scale(ab, d1 / (d1 - d3)));
+    }
+
+    let cp = sub(p, c);
+    let d5 = dot(ab, cp);
+    let d6 = dot(ac, cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return add(a, scale(ac, d2 / (d2 - d6)));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let bc = sub(c, b);
+        return add(b, scale(bc, (d4 - d3) / ((d4 - d3) + (d5 - d6))));
+    }
+
+    // Inside the face
+    let denominator = 1.0 / (va + vb + vc);
+    add(
+        a,
+        add(scale(ab, vb * denominator), scale(ac, vc * denominator)),
+    )
+}
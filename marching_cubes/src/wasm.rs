@@ -0,0 +1,808 @@
+//! JavaScript bindings, only built with the `wasm` feature.
+
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::ScalarField;
+
+/// Falloff kernels, see [`crate::Kernel`].
+#[wasm_bindgen(js_name = Kernel)]
+#[derive(Clone, Copy)]
+pub enum JsKernel {
+    Hybrid,
+    Blinn,
+    Wyvill,
+    Murakami,
+    InverseSquare,
+}
+
+#[wasm_bindgen(js_name = Metaball)]
+#[derive(Clone, Copy)]
+pub struct JsMetaball(crate::Metaball);
+
+#[wasm_bindgen(js_class = Metaball)]
+impl JsMetaball {
+    /// `kernel` defaults to the hybrid kernel, `blobbiness` only applies to
+    /// the Blinn kernel and defaults to 1.
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        x: f32,
+        y: f32,
+        z: f32,
+        radius: f32,
+        influence: f32,
+        kernel: Option<JsKernel>,
+        blobbiness: Option<f32>,
+    ) -> JsMetaball {
+        let kernel = match kernel.unwrap_or(JsKernel::Hybrid) {
+            JsKernel::Blinn => crate::Kernel::Blinn {
+                blobbiness: blobbiness.unwrap_or(1.0),
+            },
+            JsKernel::Wyvill => crate::Kernel::Wyvill,
+            JsKernel::Murakami => crate::Kernel::Murakami,
+            JsKernel::InverseSquare => crate::Kernel::InverseSquare,
+            JsKernel::Hybrid => crate::Kernel::Hybrid,
+        };
+
+        JsMetaball(crate::Metaball::with_kernel(
+            x, y, z, radius, influence, kernel,
+        ))
+    }
+
+    /// A meta-capsule around the segment from `a` to `b`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn capsule(
+        ax: f32,
+        ay: f32,
+        az: f32,
+        bx: f32,
+        by: f32,
+        bz: f32,
+        radius: f32,
+        influence: f32,
+    ) -> JsMetaball {
+        JsMetaball(crate::Metaball::capsule(
+            (ax, ay, az),
+            (bx, by, bz),
+            radius,
+            influence,
+        ))
+    }
+
+    /// A meta-triangle spanned by `a`, `b` and `c`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn triangle(
+        ax: f32,
+        ay: f32,
+        az: f32,
+        bx: f32,
+        by: f32,
+        bz: f32,
+        cx: f32,
+        cy: f32,
+        cz: f32,
+        radius: f32,
+        influence: f32,
+    ) -> JsMetaball {
+        JsMetaball(crate::Metaball::triangle(
+            (ax, ay, az),
+            (bx, by, bz),
+            (cx, cy, cz),
+            radius,
+            influence,
+        ))
+    }
+
+    /// Negative balls subtract from the field instead of adding to it.
+    #[wasm_bindgen(getter)]
+    pub fn negative(&self) -> bool {
+        self.0.negative
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_negative(&mut self, negative: bool) {
+        self.0.negative = negative;
+    }
+
+    /// `rgba` color, blended into the mesh's per-vertex colors.
+    pub fn set_color(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.0.color = (r, g, b, a);
+    }
+
+    /// Stretches the ball along its local axes and orients it by the unit
+    /// quaternion `(qx, qy, qz, qw)`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_orientation(
+        &mut self,
+        sx: f32,
+        sy: f32,
+        sz: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) {
+        self.0.scale = (sx, sy, sz);
+        self.0.rotation = crate::metaball::quaternion_to_matrix((qx, qy, qz, qw));
+    }
+}
+
+/// Signed distance primitives, see [`crate::Sdf`].
+#[wasm_bindgen(js_name = Sdf)]
+#[derive(Clone, Copy)]
+pub struct JsSdf(crate::Sdf);
+
+#[wasm_bindgen(js_class = Sdf)]
+impl JsSdf {
+    pub fn sphere(x: f32, y: f32, z: f32, radius: f32) -> JsSdf {
+        JsSdf(crate::Sdf::sphere((x, y, z), radius))
+    }
+
+    #[wasm_bindgen(js_name = box)]
+    pub fn cuboid(x: f32, y: f32, z: f32, half_x: f32, half_y: f32, half_z: f32) -> JsSdf {
+        JsSdf(crate::Sdf::cuboid((x, y, z), (half_x, half_y, half_z)))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn round_box(
+        x: f32,
+        y: f32,
+        z: f32,
+        half_x: f32,
+        half_y: f32,
+        half_z: f32,
+        rounding: f32,
+    ) -> JsSdf {
+        JsSdf(crate::Sdf::round_box(
+            (x, y, z),
+            (half_x, half_y, half_z),
+            rounding,
+        ))
+    }
+
+    pub fn torus(x: f32, y: f32, z: f32, major_radius: f32, minor_radius: f32) -> JsSdf {
+        JsSdf(crate::Sdf::torus((x, y, z), major_radius, minor_radius))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn capsule(ax: f32, ay: f32, az: f32, bx: f32, by: f32, bz: f32, radius: f32) -> JsSdf {
+        JsSdf(crate::Sdf::capsule((ax, ay, az), (bx, by, bz), radius))
+    }
+
+    pub fn cylinder(x: f32, y: f32, z: f32, radius: f32, half_height: f32) -> JsSdf {
+        JsSdf(crate::Sdf::cylinder((x, y, z), radius, half_height))
+    }
+
+    pub fn cone(
+        x: f32,
+        y: f32,
+        z: f32,
+        half_height: f32,
+        bottom_radius: f32,
+        top_radius: f32,
+    ) -> JsSdf {
+        JsSdf(crate::Sdf::cone(
+            (x, y, z),
+            half_height,
+            bottom_radius,
+            top_radius,
+        ))
+    }
+
+    pub fn plane(nx: f32, ny: f32, nz: f32, offset: f32) -> JsSdf {
+        JsSdf(crate::Sdf::plane((nx, ny, nz), offset))
+    }
+
+    pub fn ellipsoid(x: f32, y: f32, z: f32, radius_x: f32, radius_y: f32, radius_z: f32) -> JsSdf {
+        JsSdf(crate::Sdf::ellipsoid(
+            (x, y, z),
+            (radius_x, radius_y, radius_z),
+        ))
+    }
+
+    pub fn hex_prism(x: f32, y: f32, z: f32, radius: f32, half_height: f32) -> JsSdf {
+        JsSdf(crate::Sdf::hex_prism((x, y, z), radius, half_height))
+    }
+
+    /// Signed distance to the surface, negative inside.
+    pub fn distance(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.0.distance(x, y, z)
+    }
+}
+
+/// Booleans for [`JsField::csg`], see [`crate::Boolean`].
+#[wasm_bindgen(js_name = Boolean)]
+#[derive(Clone, Copy)]
+pub enum JsBoolean {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Blends for [`JsField::csg`], see [`crate::Blend`].
+#[wasm_bindgen(js_name = Blend)]
+#[derive(Clone, Copy)]
+pub enum JsBlend {
+    Hard,
+    Polynomial,
+    Exponential,
+    Chamfer,
+    Round,
+}
+
+/// Noise bases for [`JsField::noise`], see [`crate::Basis`].
+#[wasm_bindgen(js_name = NoiseBasis)]
+#[derive(Clone, Copy)]
+pub enum JsBasis {
+    Perlin,
+    Simplex,
+    Value,
+    Worley,
+}
+
+/// Octave sums for [`JsField::noise`], see [`crate::Fractal`].
+#[wasm_bindgen(js_name = Fractal)]
+#[derive(Clone, Copy)]
+pub enum JsFractal {
+    Fbm,
+    Ridged,
+    Turbulence,
+}
+
+/// Heightmap sampling for [`JsField::terrain`], see
+/// [`crate::Interpolation`].
+#[wasm_bindgen(js_name = Interpolation)]
+#[derive(Clone, Copy)]
+pub enum JsInterpolation {
+    Bilinear,
+    Bicubic,
+}
+
+/// Surface extraction algorithms, see [`crate::Mode`].
+#[wasm_bindgen(js_name = Mode)]
+#[derive(Clone, Copy)]
+pub enum JsMode {
+    MarchingCubes,
+    MarchingTetrahedra,
+    MarchingCubes33,
+    SurfaceNets,
+    /// Takes the singular value threshold as its parameter, `0.1` when left
+    /// out.
+    DualContouring,
+    /// Takes the feature angle in radians as its parameter, `0.5` when left
+    /// out.
+    ExtendedMarchingCubes,
+}
+
+/// Any field, built up into a tree from metaballs, primitives and CSG nodes.
+#[wasm_bindgen(js_name = Field)]
+#[derive(Clone)]
+pub struct JsField(Rc<dyn ScalarField>);
+
+#[wasm_bindgen(js_class = Field)]
+impl JsField {
+    /// The metaball blend with its `level` isosurface moved to zero, so it
+    /// lines up with the primitives in CSG trees.
+    #[allow(clippy::boxed_local)]
+    pub fn metaballs(metaballs: Box<[JsMetaball]>, level: f32) -> JsField {
+        let metaballs = unwrap_metaballs(&metaballs);
+        JsField(Rc::new(crate::Isolevel::new(
+            metaballs.into_boxed_slice(),
+            level,
+        )))
+    }
+
+    pub fn sdf(sdf: &JsSdf) -> JsField {
+        JsField(Rc::new(sdf.0))
+    }
+
+    /// Combines two fields, `radius` being the blend size for everything but
+    /// hard blends; a radius of zero or less joins them hard.
+    pub fn csg(
+        a: &JsField,
+        b: &JsField,
+        boolean: JsBoolean,
+        blend: JsBlend,
+        radius: f32,
+    ) -> JsField {
+        let boolean = match boolean {
+            JsBoolean::Union => crate::Boolean::Union,
+            JsBoolean::Intersection => crate::Boolean::Intersection,
+            JsBoolean::Difference => crate::Boolean::Difference,
+        };
+        let blend = match blend {
+            JsBlend::Hard => crate::Blend::Hard,
+            JsBlend::Polynomial => crate::Blend::Polynomial { radius },
+            JsBlend::Exponential => crate::Blend::Exponential { radius },
+            JsBlend::Chamfer => crate::Blend::Chamfer { radius },
+            JsBlend::Round => crate::Blend::Round { radius },
+        };
+
+        JsField(Rc::new(crate::Csg::new(
+            a.0.clone(),
+            b.0.clone(),
+            boolean,
+            blend,
+        )))
+    }
+
+    /// Trilinearly interpolated voxel data, see [`marching_cubes_volume`] for
+    /// the layout.
+    #[allow(clippy::too_many_arguments)]
+    pub fn volume(
+        values: Vec<f32>,
+        nx: usize,
+        ny: usize,
+        nz: usize,
+        spacing_x: f32,
+        spacing_y: f32,
+        spacing_z: f32,
+    ) -> Result<JsField, JsError> {
+        let samples = volume_samples(values, nx, ny, nz, spacing_x, spacing_y, spacing_z)?;
+        Ok(JsField(Rc::new(samples)))
+    }
+
+    /// Ground below a `columns * rows` heightmap stretched over `size`, x
+    /// fastest. Displace it with noise for overhangs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn terrain(
+        heights: Vec<f32>,
+        columns: usize,
+        rows: usize,
+        size_x: f32,
+        size_y: f32,
+        size_z: f32,
+        interpolation: JsInterpolation,
+    ) -> Result<JsField, JsError> {
+        let heightmap = crate::Heightmap::try_new(columns, rows, heights)
+            .map_err(|error| JsError::new(&error.to_string()))?;
+        Ok(JsField(Rc::new(terrain(
+            heightmap,
+            size_x,
+            size_y,
+            size_z,
+            interpolation,
+        ))))
+    }
+
+    /// [`JsField::terrain`] from the bytes of a PGM image, heights scaled to
+    /// `[0, size_y]`.
+    pub fn terrain_pgm(
+        bytes: &[u8],
+        size_x: f32,
+        size_y: f32,
+        size_z: f32,
+        interpolation: JsInterpolation,
+    ) -> Result<JsField, JsError> {
+        let heightmap =
+            crate::Heightmap::from_pgm(bytes).map_err(|error| JsError::new(&error.to_string()))?;
+        Ok(JsField(Rc::new(terrain(
+            heightmap,
+            size_x,
+            size_y,
+            size_z,
+            interpolation,
+        ))))
+    }
+
+    /// Seeded noise, `octaves` of it summed as `fractal` says.
+    #[allow(clippy::too_many_arguments)]
+    pub fn noise(
+        basis: JsBasis,
+        fractal: JsFractal,
+        seed: u32,
+        frequency: f32,
+        octaves: u32,
+        lacunarity: f32,
+        gain: f32,
+        amplitude: f32,
+    ) -> JsField {
+        let basis = match basis {
+            JsBasis::Perlin => crate::Basis::Perlin,
+            JsBasis::Simplex => crate::Basis::Simplex,
+            JsBasis::Value => crate::Basis::Value,
+            JsBasis::Worley => crate::Basis::Worley,
+        };
+        let fractal = match fractal {
+            JsFractal::Fbm => crate::Fractal::Fbm,
+            JsFractal::Ridged => crate::Fractal::Ridged,
+            JsFractal::Turbulence => crate::Fractal::Turbulence,
+        };
+
+        let mut noise =
+            crate::Noise::fractal(basis, fractal, seed, frequency, octaves, lacunarity, gain);
+        noise.amplitude = amplitude;
+        JsField(Rc::new(noise))
+    }
+
+    /// Scales, rotates by the unit quaternion `(qx, qy, qz, qw)` and then
+    /// translates the field. Fails if that collapses space, like a zero scale
+    /// does.
+    #[allow(clippy::too_many_arguments)]
+    pub fn transform(
+        &self,
+        tx: f32,
+        ty: f32,
+        tz: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+        sx: f32,
+        sy: f32,
+        sz: f32,
+    ) -> Result<JsField, JsError> {
+        let transform =
+            crate::Transform::try_new(self.0.clone(), (tx, ty, tz), (qx, qy, qz, qw), (sx, sy, sz))
+                .ok_or_else(|| JsError::new("transform collapses space, like a zero scale"))?;
+        Ok(JsField(Rc::new(transform)))
+    }
+
+    /// Repeats the field every `period` units, a period of zero leaving the
+    /// axis alone. `limit` caps the number of copies per side on all axes.
+    pub fn repeat(&self, px: f32, py: f32, pz: f32, limit: Option<u32>) -> JsField {
+        JsField(Rc::new(crate::Repeat::new(
+            self.0.clone(),
+            (px, py, pz),
+            limit.map(|limit| (limit, limit, limit)),
+        )))
+    }
+
+    pub fn mirror(&self, x: bool, y: bool, z: bool) -> JsField {
+        JsField(Rc::new(crate::Mirror::new(self.0.clone(), (x, y, z))))
+    }
+
+    pub fn twist(&self, rate: f32) -> JsField {
+        JsField(Rc::new(crate::Twist::new(self.0.clone(), rate)))
+    }
+
+    pub fn bend(&self, rate: f32) -> JsField {
+        JsField(Rc::new(crate::Bend::new(self.0.clone(), rate)))
+    }
+
+    pub fn displace(&self, displacement: &JsField, amount: f32) -> JsField {
+        JsField(Rc::new(crate::Displace::new(
+            self.0.clone(),
+            displacement.0.clone(),
+            amount,
+        )))
+    }
+
+    pub fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.0.value(x, y, z)
+    }
+}
+
+#[wasm_bindgen(js_name = Grid)]
+#[derive(Clone, Copy)]
+pub struct JsGrid(crate::Grid);
+
+#[wasm_bindgen(js_class = Grid)]
+impl JsGrid {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        min_x: f32,
+        min_y: f32,
+        min_z: f32,
+        max_x: f32,
+        max_y: f32,
+        max_z: f32,
+        nx: usize,
+        ny: usize,
+        nz: usize,
+    ) -> Result<JsGrid, JsError> {
+        if nx == 0 || ny == 0 || nz == 0 {
+            return Err(JsError::new(&format!(
+                "expected at least one cell along each axis, found {nx}x{ny}x{nz}"
+            )));
+        }
+
+        let bounds = crate::Bounds::new((min_x, min_y, min_z), (max_x, max_y, max_z));
+        Ok(JsGrid(crate::Grid::new(bounds, nx, ny, nz)))
+    }
+
+    /// See [`crate::Grid::fit`], `undefined` for unbounded fields.
+    pub fn fit(field: &JsField, resolution: usize) -> Option<JsGrid> {
+        crate::Grid::fit(&*field.0, resolution).map(JsGrid)
+    }
+}
+
+#[wasm_bindgen(js_name = GridData)]
+pub struct JsGridData(crate::GridData);
+
+#[wasm_bindgen(js_class = GridData)]
+impl JsGridData {
+    #[wasm_bindgen(getter)]
+    pub fn vertices(&self) -> Vec<f32> {
+        self.0.vertices.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn indices(&self) -> Vec<u32> {
+        self.0.indices.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn normals(&self) -> Vec<f32> {
+        self.0.normals.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn colors(&self) -> Vec<f32> {
+        self.0.colors.clone()
+    }
+}
+
+fn unwrap_metaballs(metaballs: &[JsMetaball]) -> Vec<crate::Metaball> {
+    metaballs.iter().map(|ball| ball.0).collect()
+}
+
+#[wasm_bindgen]
+#[allow(clippy::boxed_local)] // wasm-bindgen hands JS arrays over as boxed slices
+pub fn marching_cubes(
+    resolution: usize,
+    metaballs: Box<[JsMetaball]>,
+    threshold: f32,
+) -> JsGridData {
+    let metaballs = unwrap_metaballs(&metaballs);
+    JsGridData(crate::marching_cubes(resolution, &metaballs, threshold))
+}
+
+/// Like `marching_cubes`, but sampling an arbitrary box with its own cell counts.
+///
+/// With `closed` set the mesh is capped where it leaves the box. `mode` picks the
+/// extraction algorithm, marching cubes when left out, and `mode_parameter`
+/// tunes the modes that take one.
+#[wasm_bindgen]
+#[allow(clippy::boxed_local)]
+pub fn marching_cubes_grid(
+    grid: &JsGrid,
+    metaballs: Box<[JsMetaball]>,
+    threshold: f32,
+    closed: bool,
+    mode: Option<JsMode>,
+    mode_parameter: Option<f32>,
+) -> JsGridData {
+    let metaballs = unwrap_metaballs(&metaballs);
+    let options = mesh_options(closed, mode, mode_parameter);
+    JsGridData(crate::polygonize_with(
+        &metaballs[..],
+        &grid.0,
+        threshold,
+        &options,
+    ))
+}
+
+#[wasm_bindgen]
+#[allow(clippy::boxed_local)]
+pub fn visualize_sdf(
+    resolution: usize,
+    metaballs: Box<[JsMetaball]>,
+    threshold: f32,
+) -> JsGridData {
+    if resolution == 0 {
+        return JsGridData(crate::GridData::default());
+    }
+    let metaballs = unwrap_metaballs(&metaballs);
+    let grid = crate::Grid::unit_cube(resolution);
+    JsGridData(crate::visualize_sdf(&metaballs[..], &grid, threshold))
+}
+
+#[wasm_bindgen]
+#[allow(clippy::boxed_local)]
+pub fn visualize_sdf_grid(
+    grid: &JsGrid,
+    metaballs: Box<[JsMetaball]>,
+    threshold: f32,
+) -> JsGridData {
+    let metaballs = unwrap_metaballs(&metaballs);
+    JsGridData(crate::visualize_sdf(&metaballs[..], &grid.0, threshold))
+}
+
+/// Meshes a signed distance primitive. The surface sits where the distance is
+/// `-threshold`, so `0.0` meshes the primitive itself.
+#[wasm_bindgen]
+pub fn marching_cubes_sdf(
+    grid: &JsGrid,
+    sdf: &JsSdf,
+    threshold: f32,
+    closed: bool,
+    mode: Option<JsMode>,
+    mode_parameter: Option<f32>,
+) -> JsGridData {
+    let options = mesh_options(closed, mode, mode_parameter);
+    JsGridData(crate::polygonize_with(&sdf.0, &grid.0, threshold, &options))
+}
+
+#[wasm_bindgen]
+pub fn marching_cubes_field(
+    grid: &JsGrid,
+    field: &JsField,
+    threshold: f32,
+    closed: bool,
+    mode: Option<JsMode>,
+    mode_parameter: Option<f32>,
+) -> JsGridData {
+    let options = mesh_options(closed, mode, mode_parameter);
+    JsGridData(crate::polygonize_with(
+        &*field.0, &grid.0, threshold, &options,
+    ))
+}
+
+/// Options for a mode and its parameter, which is ignored by the modes
+/// without one.
+fn mesh_options(
+    closed: bool,
+    mode: Option<JsMode>,
+    mode_parameter: Option<f32>,
+) -> crate::MeshOptions {
+    let mode = match mode.unwrap_or(JsMode::MarchingCubes) {
+        JsMode::MarchingCubes => crate::Mode::MarchingCubes,
+        JsMode::MarchingTetrahedra => crate::Mode::MarchingTetrahedra,
+        JsMode::MarchingCubes33 => crate::Mode::MarchingCubes33,
+        JsMode::SurfaceNets => crate::Mode::SurfaceNets,
+        JsMode::DualContouring => crate::Mode::DualContouring {
+            singular_value_threshold: mode_parameter.unwrap_or(0.1),
+        },
+        JsMode::ExtendedMarchingCubes => crate::Mode::ExtendedMarchingCubes {
+            feature_angle: mode_parameter.unwrap_or(0.5),
+        },
+    };
+
+    crate::MeshOptions { closed, mode }
+}
+
+fn terrain(
+    heightmap: crate::Heightmap,
+    size_x: f32,
+    size_y: f32,
+    size_z: f32,
+    interpolation: JsInterpolation,
+) -> crate::Terrain {
+    let mut terrain = crate::Terrain::new(heightmap, (size_x, size_y, size_z));
+    terrain.interpolation = match interpolation {
+        JsInterpolation::Bilinear => crate::Interpolation::Bilinear,
+        JsInterpolation::Bicubic => crate::Interpolation::Bicubic,
+    };
+    terrain
+}
+
+/// Wraps `nx * ny * nz` voxels, checked against the number of values
+/// passed so a wrong-length array is an error rather than a trap.
+fn volume_samples(
+    values: Vec<f32>,
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    spacing_x: f32,
+    spacing_y: f32,
+    spacing_z: f32,
+) -> Result<crate::Samples, JsError> {
+    if nx == 0 || ny == 0 || nz == 0 {
+        return Err(JsError::new(&format!(
+            "empty volume of {nx}x{ny}x{nz} voxels"
+        )));
+    }
+
+    let grid = crate::Grid::from_points(
+        (0.0, 0.0, 0.0),
+        (nx, ny, nz),
+        (spacing_x, spacing_y, spacing_z),
+    );
+    crate::Samples::try_new(grid, values).map_err(|error| JsError::new(&error.to_string()))
+}
+
+/// Meshes caller-supplied voxel data: `nx * ny * nz` values, x-fastest, then
+/// y, then z, `spacing` apart with the first voxel at the origin.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn marching_cubes_volume(
+    values: Vec<f32>,
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    spacing_x: f32,
+    spacing_y: f32,
+    spacing_z: f32,
+    threshold: f32,
+    closed: bool,
+    mode: Option<JsMode>,
+    mode_parameter: Option<f32>,
+) -> Result<JsGridData, JsError> {
+    let samples = volume_samples(values, nx, ny, nz, spacing_x, spacing_y, spacing_z)?;
+    let options = mesh_options(closed, mode, mode_parameter);
+    Ok(JsGridData(crate::polygonize_samples(
+        &samples, threshold, &options,
+    )))
+}
+
+/// [`marching_cubes_volume`] for `Uint8Array` data.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn marching_cubes_volume_u8(
+    values: &[u8],
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    spacing_x: f32,
+    spacing_y: f32,
+    spacing_z: f32,
+    threshold: f32,
+    closed: bool,
+    mode: Option<JsMode>,
+    mode_parameter: Option<f32>,
+) -> Result<JsGridData, JsError> {
+    let samples = volume_samples(
+        values.iter().map(|&value| value as f32).collect(),
+        nx,
+        ny,
+        nz,
+        spacing_x,
+        spacing_y,
+        spacing_z,
+    )?;
+    let options = mesh_options(closed, mode, mode_parameter);
+    Ok(JsGridData(crate::polygonize_samples(
+        &samples, threshold, &options,
+    )))
+}
+
+/// [`marching_cubes_volume`] for `Uint16Array` data.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn marching_cubes_volume_u16(
+    values: &[u16],
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    spacing_x: f32,
+    spacing_y: f32,
+    spacing_z: f32,
+    threshold: f32,
+    closed: bool,
+    mode: Option<JsMode>,
+    mode_parameter: Option<f32>,
+) -> Result<JsGridData, JsError> {
+    let samples = volume_samples(
+        values.iter().map(|&value| value as f32).collect(),
+        nx,
+        ny,
+        nz,
+        spacing_x,
+        spacing_y,
+        spacing_z,
+    )?;
+    let options = mesh_options(closed, mode, mode_parameter);
+    Ok(JsGridData(crate::polygonize_samples(
+        &samples, threshold, &options,
+    )))
+}
+
+/// Meshes model `model` of a MagicaVoxel `.vox` file, smoothed by a Gaussian
+/// blur of `blur` voxels, with per-vertex colors from its palette.
+///
+/// `threshold` 0.5 puts the surface halfway between filled and empty voxels.
+#[wasm_bindgen]
+pub fn marching_cubes_vox(
+    bytes: &[u8],
+    model: usize,
+    blur: f32,
+    threshold: f32,
+) -> Result<JsGridData, JsError> {
+    let vox = crate::formats::read_vox(bytes).map_err(|error| JsError::new(&error.to_string()))?;
+    let model = vox
+        .models
+        .get(model)
+        .ok_or_else(|| JsError::new("no such model in the .vox file"))?;
+
+    let volume = crate::formats::VoxelVolume::new(model, &vox.palette, blur)
+        .map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(JsGridData(crate::polygonize(
+        &volume,
+        &volume.grid(),
+        threshold,
+    )))
+}
@@ -0,0 +1,25 @@
+use marching_cubes::{marching_cubes, Metaball};
+
+fn max_coordinate(vertices: &[f32]) -> f32 {
+    vertices.iter().copied().fold(f32::MIN, f32::max)
+}
+
+fn min_coordinate(vertices: &[f32]) -> f32 {
+    vertices.iter().copied().fold(f32::MAX, f32::min)
+}
+
+#[test]
+fn sphere_at_far_corner_is_not_clipped() {
+    let far = marching_cubes(32, &[Metaball::new(0.9, 0.9, 0.9, 0.1, 1.0)], 0.5);
+    let near = marching_cubes(32, &[Metaball::new(0.1, 0.1, 0.1, 0.1, 1.0)], 0.5);
+
+    // The surface runs out of the domain on all three far faces, so it has to
+    // reach them instead of stopping a cell short
+    assert!(!far.indices.is_empty());
+    assert!((max_coordinate(&far.vertices) - 1.0).abs() < 1e-6);
+
+    // The far corner must mesh exactly like its mirror image at the origin
+    assert_eq!(min_coordinate(&near.vertices), 0.0);
+    assert_eq!(far.vertices.len(), near.vertices.len());
+    assert_eq!(far.indices.len(), near.indices.len());
+}
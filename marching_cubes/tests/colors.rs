@@ -0,0 +1,56 @@
+use marching_cubes::{polygonize_with, Grid, MeshOptions, Mode, ScalarField, Sdf};
+
+/// A sphere painted red on its positive x half only.
+struct HalfPainted(Sdf);
+
+impl ScalarField for HalfPainted {
+    fn value(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.0.value(x, y, z)
+    }
+
+    fn color(&self, x: f32, _y: f32, _z: f32) -> Option<(f32, f32, f32, f32)> {
+        (x > 0.5).then_some((1.0, 0.0, 0.0, 1.0))
+    }
+}
+
+#[test]
+fn colors_stay_aligned_with_vertices() {
+    let field = HalfPainted(Sdf::sphere((0.5, 0.5, 0.5), 0.3));
+    let grid = Grid::unit_cube(16);
+    for mode in [
+        Mode::MarchingCubes,
+        Mode::MarchingTetrahedra,
+        Mode::MarchingCubes33,
+        Mode::SurfaceNets,
+        Mode::DualContouring {
+            singular_value_threshold: 0.1,
+        },
+        Mode::ExtendedMarchingCubes { feature_angle: 0.5 },
+    ] {
+        let options = MeshOptions {
+            closed: false,
+            mode,
+        };
+        let mesh = polygonize_with(&field, &grid, 0.0, &options);
+        assert_eq!(mesh.colors.len() / 4, mesh.vertices.len() / 3, "{mode:?}");
+
+        // Unpainted vertices are white, including the ones before the first
+        // painted one
+        for (vertex, color) in mesh.vertices.chunks(3).zip(mesh.colors.chunks(4)) {
+            let expected = if vertex[0] > 0.5 {
+                [1.0, 0.0, 0.0, 1.0]
+            } else {
+                [1.0; 4]
+            };
+            assert_eq!(color, expected, "{mode:?}");
+        }
+    }
+}
+
+#[test]
+fn fields_without_colors_leave_them_empty() {
+    let sphere = Sdf::sphere((0.5, 0.5, 0.5), 0.3);
+    let mesh = polygonize_with(&sphere, &Grid::unit_cube(8), 0.0, &MeshOptions::default());
+    assert!(!mesh.vertices.is_empty());
+    assert!(mesh.colors.is_empty());
+}
@@ -0,0 +1,128 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+
+use marching_cubes::{finite_difference, GridData, ScalarField};
+
+/// 500 points scattered through the cube from `-extent` to `extent`, off
+/// the integer lattice and the coordinate planes.
+pub fn points(extent: f32) -> impl Iterator<Item = (f32, f32, f32)> {
+    (0..500).map(move |i| {
+        let i = i as f32 + 0.5;
+        let coordinate = |fraction: f32| ((i * fraction).fract() * 2.0 - 1.0) * extent;
+        (
+            coordinate(0.618_034),
+            coordinate(0.414_214),
+            coordinate(0.732_051),
+        )
+    })
+}
+
+/// Asserts the analytic gradient agrees with central differences to within
+/// `tolerance`, relative to the gradient's size, at each of `points` where
+/// the field is smooth. Creases, where the gradient jumps within a few
+/// thousandths, make central differences average their sides and are
+/// skipped. Returns how many points were checked.
+pub fn assert_gradients<F: ScalarField + ?Sized>(
+    field: &F,
+    points: impl IntoIterator<Item = (f32, f32, f32)>,
+    tolerance: f32,
+    case: &str,
+) -> usize {
+    let mut checked = 0;
+    for (x, y, z) in points {
+        let analytic = field.gradient(x, y, z).unwrap();
+        let scale = 1.0 + analytic.0.abs() + analytic.1.abs() + analytic.2.abs();
+        let creased = [
+            (0.002, 0.0, 0.0),
+            (-0.002, 0.0, 0.0),
+            (0.0, 0.002, 0.0),
+            (0.0, -0.002, 0.0),
+            (0.0, 0.0, 0.002),
+            (0.0, 0.0, -0.002),
+        ]
+        .iter()
+        .any(|&(dx, dy, dz)| {
+            let gradient = field.gradient(x + dx, y + dy, z + dz).unwrap();
+            (gradient.0 - analytic.0).abs()
+                + (gradient.1 - analytic.1).abs()
+                + (gradient.2 - analytic.2).abs()
+                > 0.1 * scale
+        });
+        if creased {
+            continue;
+        }
+
+        let numeric = finite_difference(field, x, y, z);
+        let error = (analytic.0 - numeric.0).abs()
+            + (analytic.1 - numeric.1).abs()
+            + (analytic.2 - numeric.2).abs();
+        assert!(
+            error < tolerance * scale,
+            "{case} at {:?}: {analytic:?} vs {numeric:?}",
+            (x, y, z)
+        );
+        checked += 1;
+    }
+    checked
+}
+
+/// Asserts every edge is used once in each direction, so the mesh is
+/// closed, manifold and consistently wound, and returns its Euler
+/// characteristic.
+pub fn assert_closed(mesh: &GridData, case: &str) -> i64 {
+    let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+    for triangle in mesh.indices.chunks(3) {
+        for k in 0..3 {
+            *edges
+                .entry((triangle[k], triangle[(k + 1) % 3]))
+                .or_default() += 1;
+        }
+    }
+    for (&(a, b), &count) in &edges {
+        assert_eq!(count, 1, "{case}: edge {a}-{b} used {count} times");
+        assert_eq!(edges.get(&(b, a)), Some(&1), "{case}: edge {a}-{b} is open");
+    }
+
+    let vertices: HashSet<u32> = mesh.indices.iter().copied().collect();
+    vertices.len() as i64 - edges.len() as i64 / 2 + mesh.indices.len() as i64 / 3
+}
+
+/// The position of vertex `index`.
+pub fn vertex(mesh: &GridData, index: u32) -> (f32, f32, f32) {
+    let i = index as usize * 3;
+    (mesh.vertices[i], mesh.vertices[i + 1], mesh.vertices[i + 2])
+}
+
+/// The mesh with vertices at the same position merged and the triangles
+/// that collapse dropped, as any welding would.
+pub fn weld(mesh: &GridData) -> GridData {
+    let mut welded = HashMap::new();
+    let mut vertices = Vec::new();
+    let ids: Vec<u32> = (0..mesh.vertices.len() as u32 / 3)
+        .map(|index| {
+            let (x, y, z) = vertex(mesh, index);
+            *welded
+                .entry((x.to_bits(), y.to_bits(), z.to_bits()))
+                .or_insert_with(|| {
+                    vertices.extend([x, y, z]);
+                    vertices.len() as u32 / 3 - 1
+                })
+        })
+        .collect();
+
+    let mut indices = Vec::new();
+    for triangle in mesh.indices.chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| ids[triangle[k] as usize]);
+        if a != b && b != c && c != a {
+            indices.extend([a, b, c]);
+        }
+    }
+    GridData {
+        vertices,
+        indices,
+        ..GridData::default()
+    }
+}
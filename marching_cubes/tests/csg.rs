@@ -0,0 +1,146 @@
+mod common;
+
+use common::points;
+use marching_cubes::{Blend, Boolean, Bounds, Csg, ScalarField, Sdf};
+
+const BOOLEANS: [Boolean; 3] = [Boolean::Union, Boolean::Intersection, Boolean::Difference];
+
+const SMOOTH: [Blend; 4] = [
+    Blend::Polynomial { radius: 0.2 },
+    Blend::Exponential { radius: 0.2 },
+    Blend::Chamfer { radius: 0.2 },
+    Blend::Round { radius: 0.2 },
+];
+
+fn spheres() -> (Sdf, Sdf) {
+    (
+        Sdf::sphere((-0.2, 0.0, 0.0), 0.5),
+        Sdf::sphere((0.25, 0.1, 0.05), 0.4),
+    )
+}
+
+#[test]
+fn hard_booleans_are_min_and_max() {
+    let (a, b) = spheres();
+    for (x, y, z) in points(0.75) {
+        let (va, vb) = (a.value(x, y, z), b.value(x, y, z));
+        for (boolean, expected) in [
+            (Boolean::Union, va.max(vb)),
+            (Boolean::Intersection, va.min(vb)),
+            (Boolean::Difference, va.min(-vb)),
+        ] {
+            let csg = Csg::new(a, b, boolean, Blend::Hard);
+            assert_eq!(csg.value(x, y, z), expected, "{boolean:?}");
+        }
+    }
+}
+
+#[test]
+fn smooth_blends_only_fill_in_near_the_seam() {
+    // Half-spaces x < 0 and y < 0, meeting along the z axis
+    let a = Sdf::plane((1.0, 0.0, 0.0), 0.0);
+    let b = Sdf::plane((0.0, 1.0, 0.0), 0.0);
+
+    for blend in SMOOTH {
+        let union = Csg::union(a, b, blend);
+        let hard = Csg::union(a, b, Blend::Hard);
+        for (x, y, z) in points(0.75) {
+            // Unions only grow, and around the surface by less than the
+            // radius. Deep inside, the chamfer's distances are only bounds.
+            let (value, hard) = (union.value(x, y, z), hard.value(x, y, z));
+            assert!(value >= hard - 1e-6, "{blend:?} at {:?}", (x, y, z));
+            if hard <= 0.2 {
+                assert!(value <= hard + 0.2 + 1e-6, "{blend:?} at {:?}", (x, y, z));
+            }
+        }
+
+        // On either surface further than the radius from the other, the
+        // surface is left where it was
+        for far in [0.25, 0.5] {
+            for (x, y) in [(0.0, far), (far, 0.0)] {
+                let value = union.value(x, y, 0.3);
+                let tolerance = match blend {
+                    Blend::Exponential { radius } => radius * (-far / radius).exp(),
+                    _ => 1e-6,
+                };
+                assert!(value.abs() <= tolerance, "{blend:?}: {value}");
+            }
+        }
+
+        // While the crease itself is filled in
+        assert!(union.value(0.02, 0.02, 0.0) > 0.0, "{blend:?}");
+    }
+}
+
+#[test]
+fn blends_without_a_radius_are_hard() {
+    let (a, b) = spheres();
+    for radius in [0.0, -0.2, f32::NAN] {
+        for blend in [
+            Blend::Polynomial { radius },
+            Blend::Exponential { radius },
+            Blend::Chamfer { radius },
+            Blend::Round { radius },
+        ] {
+            // Including against itself, where the operands are equal
+            for ((a, b), boolean) in [(a, b), (a, a)]
+                .into_iter()
+                .flat_map(|pair| BOOLEANS.map(|boolean| (pair, boolean)))
+            {
+                let csg = Csg::new(a, b, boolean, blend);
+                let hard = Csg::new(a, b, boolean, Blend::Hard);
+                assert_eq!(csg.bounds(), hard.bounds(), "{boolean:?} {blend:?}");
+                for (x, y, z) in points(0.75) {
+                    assert_eq!(csg.value(x, y, z), hard.value(x, y, z));
+                    assert_eq!(csg.gradient(x, y, z), hard.gradient(x, y, z));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn gradients_match_finite_differences() {
+    let (a, b) = spheres();
+    for boolean in BOOLEANS {
+        for blend in std::iter::once(Blend::Hard).chain(SMOOTH) {
+            let csg = Csg::new(a, b, boolean, blend);
+            let case = format!("{boolean:?} {blend:?}");
+            let checked = common::assert_gradients(&csg, points(0.75), 0.01, &case);
+            assert!(checked > 400, "{case}: only {checked} smooth points");
+        }
+    }
+}
+
+#[test]
+fn bounds_follow_the_boolean() {
+    let a = Sdf::sphere((0.0, 0.0, 0.0), 1.0);
+    let b = Sdf::sphere((1.5, 0.0, 0.0), 1.0);
+    let bounds = |boolean, blend| Csg::new(a, b, boolean, blend).bounds().unwrap();
+
+    assert_eq!(
+        bounds(Boolean::Union, Blend::Hard),
+        Bounds::new((-1.0, -1.0, -1.0), (2.5, 1.0, 1.0))
+    );
+    assert_eq!(
+        bounds(Boolean::Union, Blend::Round { radius: 0.5 }),
+        Bounds::new((-1.5, -1.5, -1.5), (3.0, 1.5, 1.5))
+    );
+    assert_eq!(
+        bounds(Boolean::Intersection, Blend::Hard),
+        Bounds::new((0.5, -1.0, -1.0), (1.0, 1.0, 1.0))
+    );
+    assert_eq!(
+        bounds(Boolean::Difference, Blend::Hard),
+        a.bounds().unwrap()
+    );
+
+    // An unbounded operand leaves the union unbounded, but not the
+    // intersection
+    let plane = Sdf::plane((0.0, 1.0, 0.0), 0.0);
+    assert_eq!(Csg::union(a, plane, Blend::Hard).bounds(), None);
+    assert_eq!(
+        Csg::intersection(a, plane, Blend::Hard).bounds(),
+        a.bounds()
+    );
+}
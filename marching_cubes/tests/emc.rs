@@ -0,0 +1,95 @@
+mod common;
+
+use common::{assert_closed, vertex, weld};
+use marching_cubes::{polygonize_with, Bounds, Grid, GridData, MeshOptions, Mode, Sdf};
+
+fn extended(closed: bool) -> MeshOptions {
+    MeshOptions {
+        closed,
+        mode: Mode::ExtendedMarchingCubes { feature_angle: 0.5 },
+    }
+}
+
+/// Distance from `point` to the nearest vertex of the mesh.
+fn nearest(mesh: &GridData, point: (f32, f32, f32)) -> f32 {
+    (0..mesh.vertices.len() as u32 / 3)
+        .map(|index| {
+            let (x, y, z) = vertex(mesh, index);
+            ((x - point.0).powi(2) + (y - point.1).powi(2) + (z - point.2).powi(2)).sqrt()
+        })
+        .fold(f32::MAX, f32::min)
+}
+
+#[test]
+fn cuboid_corners_and_edges_are_sharp() {
+    // Corners well inside cells and faces off the lattice planes, so no
+    // crossing lands on a feature by accident
+    let center = (0.033, -0.021, 0.012);
+    let half = (0.551, 0.362, 0.633);
+    let cuboid = Sdf::cuboid(center, half);
+    let grid = Grid::new(Bounds::new((-1.0, -1.0, -1.0), (1.0, 1.0, 1.0)), 20, 20, 20);
+    let mesh = polygonize_with(&cuboid, &grid, 0.0, &extended(false));
+
+    assert_closed(&weld(&mesh), "extended");
+    for corner in 0..8 {
+        let sign = |bit: usize| if corner >> bit & 1 == 1 { 1.0 } else { -1.0 };
+        let point = (
+            center.0 + sign(0) * half.0,
+            center.1 + sign(1) * half.1,
+            center.2 + sign(2) * half.2,
+        );
+        assert!(nearest(&mesh, point) < 1e-3, "corner {point:?} missed");
+    }
+
+    // Each of the twelve edges is traced by feature vertices, about one
+    // per cell it passes through
+    for axis in 0..3 {
+        for side in 0..4 {
+            let extent = [half.0, half.1, half.2];
+            let on_edge = |(x, y, z): (f32, f32, f32)| {
+                let offsets = [x - center.0, y - center.1, z - center.2];
+                let others = [(axis + 1) % 3, (axis + 2) % 3];
+                others
+                    .iter()
+                    .zip([side & 1, side >> 1])
+                    .all(|(&other, bit)| {
+                        let sign = if bit == 1 { 1.0 } else { -1.0 };
+                        (offsets[other] - sign * extent[other]).abs() < 1e-3
+                    })
+                    && offsets[axis].abs() <= extent[axis] + 1e-3
+            };
+            let count = (0..mesh.vertices.len() as u32 / 3)
+                .filter(|&index| on_edge(vertex(&mesh, index)))
+                .count();
+            let cells = (2.0 * extent[axis] / 0.1) as usize;
+            assert!(count >= cells, "edge {axis}/{side} has {count} vertices");
+        }
+    }
+
+    // Nothing is pulled off the surface
+    for index in 0..mesh.vertices.len() as u32 / 3 {
+        let (x, y, z) = vertex(&mesh, index);
+        assert!(cuboid.distance(x, y, z).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn closed_mesh_is_manifold_where_it_leaves_the_domain() {
+    // Shapes sticking out of the domain on several sides, so caps meet
+    // creases of the surface and each other
+    let grid = Grid::new(Bounds::new((-1.0, -1.0, -1.0), (1.0, 1.0, 1.0)), 16, 16, 16);
+    for shape in [
+        Sdf::cuboid((0.31, 0.27, 0.0), (0.93, 0.55, 1.4)),
+        Sdf::cuboid((0.0, 0.0, 0.0), (1.3, 0.7, 1.3)),
+        Sdf::sphere((0.5, 0.4, 0.3), 0.9),
+    ] {
+        let mesh = polygonize_with(&shape, &grid, 0.0, &extended(true));
+        assert!(!mesh.indices.is_empty());
+        assert_closed(&weld(&mesh), "extended");
+
+        // Caps stay on the domain's faces
+        for &coordinate in &mesh.vertices {
+            assert!(coordinate.abs() <= 1.0 + 1e-6);
+        }
+    }
+}
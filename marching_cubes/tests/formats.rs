@@ -0,0 +1,335 @@
+use marching_cubes::formats::{
+    open_nrrd, read_mrc, read_nrrd, read_raw, read_vtk, DataType, Endian, RawLayout, VolumeError,
+};
+
+/// NRRD header lines followed by the blank line and `data`.
+fn nrrd(header: &str, data: &[u8]) -> Vec<u8> {
+    let mut bytes = format!("NRRD0004\n{header}\n\n").into_bytes();
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// MRC file with the given header words and voxel bytes.
+fn mrc(words: &[(usize, i32)], stamp: u8, endian: Endian, data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0u8; 1024];
+    for &(index, word) in words {
+        let word = match endian {
+            Endian::Little => word.to_le_bytes(),
+            Endian::Big => word.to_be_bytes(),
+        };
+        bytes[4 * index..4 * index + 4].copy_from_slice(&word);
+    }
+    bytes[212] = stamp;
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn raw_reads_big_endian_voxels_after_a_header() {
+    let layout = RawLayout {
+        endian: Endian::Big,
+        spacing: (0.5, 1.0, 2.0),
+        origin: (1.0, 2.0, 3.0),
+        header: 3,
+        ..RawLayout::new((2, 2, 1), DataType::U16)
+    };
+    let mut bytes = vec![0xff; 3];
+    for value in [1u16, 2, 300, 65535] {
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let samples = read_raw(&bytes, &layout).unwrap();
+    assert_eq!(samples.values, [1.0, 2.0, 300.0, 65535.0]);
+    assert_eq!(
+        (samples.grid.nx, samples.grid.ny, samples.grid.nz),
+        (1, 1, 0)
+    );
+    assert_eq!(samples.grid.bounds.min, (1.0, 2.0, 3.0));
+    assert_eq!(samples.grid.bounds.max, (1.5, 3.0, 3.0));
+}
+
+#[test]
+fn raw_rejects_short_data_and_absurd_sizes() {
+    let layout = RawLayout::new((2, 2, 2), DataType::F32);
+    assert!(matches!(
+        read_raw(&[0; 31], &layout),
+        Err(VolumeError::Truncated {
+            expected: 32,
+            found: 31
+        })
+    ));
+
+    let layout = RawLayout::new((usize::MAX, 2, 1), DataType::U8);
+    assert!(matches!(
+        read_raw(&[0; 8], &layout),
+        Err(VolumeError::Format(_))
+    ));
+    let layout = RawLayout::new((1 << 40, 1 << 22, 1), DataType::F64);
+    assert!(matches!(
+        read_raw(&[0; 8], &layout),
+        Err(VolumeError::Format(_))
+    ));
+}
+
+#[test]
+fn nrrd_reads_attached_raw_data() {
+    let mut data = Vec::new();
+    for value in [0.5f32, -1.0, 2.0, 4.0] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    let bytes = nrrd(
+        "# a comment\ntype: float\ndimension: 3\nsizes: 2 2 1\nencoding: raw\nendian: big\n\
+         space origin: (1,0,-1)\nspace directions: (2,0,0) (0,3,0) (0,0,1)",
+        &data,
+    );
+
+    let samples = read_nrrd(&bytes).unwrap();
+    assert_eq!(samples.values, [0.5, -1.0, 2.0, 4.0]);
+    assert_eq!(samples.grid.bounds.min, (1.0, 0.0, -1.0));
+    assert_eq!(samples.grid.bounds.max, (3.0, 3.0, -1.0));
+}
+
+#[test]
+fn nrrd_byte_skip_minus_one_takes_the_last_bytes() {
+    let bytes = nrrd(
+        "type: uchar\ndimension: 3\nsizes: 2 1 1\nencoding: raw\nbyte skip: -1",
+        &[9, 9, 9, 7, 8],
+    );
+    assert_eq!(read_nrrd(&bytes).unwrap().values, [7.0, 8.0]);
+
+    let bytes = nrrd(
+        "type: uchar\ndimension: 3\nsizes: 2 1 1\nencoding: raw\nbyte skip: 2",
+        &[9, 9, 7, 8],
+    );
+    assert_eq!(read_nrrd(&bytes).unwrap().values, [7.0, 8.0]);
+}
+
+#[test]
+fn nrrd_reads_ascii_data() {
+    let bytes = nrrd(
+        "type: short\ndimension: 3\nsizes: 1 2 2\nencoding: ascii\nspacings: 1 0.5 0.25",
+        b"1 -2\n3.5 4\n",
+    );
+
+    let samples = read_nrrd(&bytes).unwrap();
+    assert_eq!(samples.values, [1.0, -2.0, 3.5, 4.0]);
+    assert_eq!(samples.grid.bounds.max, (0.0, 0.5, 0.25));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn nrrd_reads_gzip_data() {
+    // gzip of the little-endian u16 values 0, 100, ..., 700
+    const GZIP: [u8; 36] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x63, 0x60, 0x48, 0x61, 0x38,
+        0xc1, 0xa0, 0xc3, 0x38, 0x81, 0xf1, 0x0b, 0x63, 0x04, 0xd3, 0x1e, 0x26, 0x00, 0xd5, 0xac,
+        0xaf, 0xfc, 0x10, 0x00, 0x00, 0x00,
+    ];
+    let bytes = nrrd(
+        "type: ushort\ndimension: 3\nsizes: 2 2 2\nencoding: gzip\nendian: little",
+        &GZIP,
+    );
+
+    let samples = read_nrrd(&bytes).unwrap();
+    assert_eq!(
+        samples.values,
+        [0.0, 100.0, 200.0, 300.0, 400.0, 500.0, 600.0, 700.0]
+    );
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn nrrd_gzip_needs_the_feature() {
+    let bytes = nrrd(
+        "type: ushort\ndimension: 3\nsizes: 2 2 2\nencoding: gzip",
+        &[0x1f, 0x8b, 0x08],
+    );
+    assert!(matches!(
+        read_nrrd(&bytes),
+        Err(VolumeError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn nrrd_rejects_truncated_data_and_overflowing_sizes() {
+    let bytes = nrrd(
+        "type: float\ndimension: 3\nsizes: 2 2 2\nencoding: raw",
+        &[0; 31],
+    );
+    assert!(matches!(
+        read_nrrd(&bytes),
+        Err(VolumeError::Truncated {
+            expected: 32,
+            found: 31
+        })
+    ));
+
+    for sizes in [
+        "2097152 2097152 2097152",
+        "4294967296 4294967296 4294967296",
+    ] {
+        for encoding in ["raw\nbyte skip: -1", "raw", "ascii"] {
+            let header = format!("type: float\ndimension: 3\nsizes: {sizes}\nencoding: {encoding}");
+            assert!(matches!(
+                read_nrrd(&nrrd(&header, &[0; 64])),
+                Err(VolumeError::Format(_) | VolumeError::Truncated { .. })
+            ));
+        }
+    }
+}
+
+#[test]
+fn nrrd_follows_detached_headers_ending_at_end_of_file() {
+    let directory =
+        std::env::temp_dir().join(format!("marching_cubes_nhdr_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("volume.raw"), [1u8, 2, 3, 4]).unwrap();
+
+    // With and without a final newline
+    for ending in ["", "\n"] {
+        let header = format!(
+            "NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2 1\nencoding: raw\ndata file: volume.raw{ending}"
+        );
+        std::fs::write(directory.join("volume.nhdr"), header).unwrap();
+        let samples = open_nrrd(directory.join("volume.nhdr")).unwrap();
+        assert_eq!(samples.values, [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn mrc_reads_little_endian_floats() {
+    let mut data = Vec::new();
+    for value in [1.0f32, 2.0, 3.0, 4.0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    let bytes = mrc(
+        &[
+            (0, 2),
+            (1, 2),
+            (2, 1),
+            (3, 2),
+            (7, 2),
+            (8, 2),
+            (9, 1),
+            (10, 4.0f32.to_bits() as i32),
+            (11, 1.0f32.to_bits() as i32),
+            (12, 1.0f32.to_bits() as i32),
+            (16, 1),
+            (17, 2),
+            (18, 3),
+            (49, 10.0f32.to_bits() as i32),
+        ],
+        0x44,
+        Endian::Little,
+        &data,
+    );
+
+    let samples = read_mrc(&bytes).unwrap();
+    assert_eq!(samples.values, [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(samples.grid.bounds.min, (10.0, 0.0, 0.0));
+    assert_eq!(samples.grid.bounds.max, (12.0, 0.5, 0.0));
+}
+
+#[test]
+fn mrc_reads_big_endian_shorts_and_undoes_the_axis_mapping() {
+    // Columns run along y and rows along x
+    let mut data = Vec::new();
+    for value in 0i16..6 {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    let bytes = mrc(
+        &[(0, 2), (1, 3), (2, 1), (3, 1), (16, 2), (17, 1), (18, 3)],
+        0x11,
+        Endian::Big,
+        &data,
+    );
+
+    let samples = read_mrc(&bytes).unwrap();
+    assert_eq!((samples.grid.nx, samples.grid.ny), (2, 1));
+    for x in 0..3 {
+        for y in 0..2 {
+            assert_eq!(samples.get(x, y, 0), (y + 2 * x) as f32);
+        }
+    }
+}
+
+#[test]
+fn mrc_rejects_truncated_files_and_absurd_sizes() {
+    assert!(matches!(
+        read_mrc(&[0; 100]),
+        Err(VolumeError::Truncated { .. })
+    ));
+
+    let bytes = mrc(
+        &[(0, 2), (1, 2), (2, 2), (3, 2)],
+        0x44,
+        Endian::Little,
+        &[0; 31],
+    );
+    assert!(matches!(
+        read_mrc(&bytes),
+        Err(VolumeError::Truncated {
+            expected: 32,
+            found: 31
+        })
+    ));
+
+    let bytes = mrc(
+        &[(0, i32::MAX), (1, i32::MAX), (2, i32::MAX), (3, 2)],
+        0x44,
+        Endian::Little,
+        &[0; 32],
+    );
+    assert!(matches!(
+        read_mrc(&bytes),
+        Err(VolumeError::Format(_) | VolumeError::Truncated { .. })
+    ));
+}
+
+#[test]
+fn vtk_reads_ascii_scalars() {
+    let bytes = b"# vtk DataFile Version 3.0\nvolume\nASCII\nDATASET STRUCTURED_POINTS\n\
+        DIMENSIONS 2 1 2\nORIGIN 1 2 3\nSPACING 0.5 1 2\nPOINT_DATA 4\n\
+        SCALARS density float 1\nLOOKUP_TABLE default\n0 1.5\n-2 3\n";
+
+    let samples = read_vtk(bytes).unwrap();
+    assert_eq!(samples.values, [0.0, 1.5, -2.0, 3.0]);
+    assert_eq!(samples.grid.bounds.min, (1.0, 2.0, 3.0));
+    assert_eq!(samples.grid.bounds.max, (1.5, 2.0, 5.0));
+}
+
+#[test]
+fn vtk_reads_big_endian_binary_scalars() {
+    let mut bytes = b"# vtk DataFile Version 3.0\n\nBINARY\nDATASET STRUCTURED_POINTS\n\
+        DIMENSIONS 2 2 1\nPOINT_DATA 4\nSCALARS density short\nLOOKUP_TABLE default\n"
+        .to_vec();
+    for value in [-1i16, 2, 300, 4] {
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    assert_eq!(read_vtk(&bytes).unwrap().values, [-1.0, 2.0, 300.0, 4.0]);
+}
+
+#[test]
+fn vtk_rejects_truncated_data_and_absurd_sizes() {
+    let header = b"# vtk DataFile Version 3.0\n\nBINARY\nDATASET STRUCTURED_POINTS\n\
+        DIMENSIONS 2 2 2\nPOINT_DATA 8\nSCALARS density float\n";
+    let mut bytes = header.to_vec();
+    bytes.extend_from_slice(&[0; 31]);
+    assert!(matches!(
+        read_vtk(&bytes),
+        Err(VolumeError::Truncated {
+            expected: 32,
+            found: 31
+        })
+    ));
+
+    let bytes = b"# vtk DataFile Version 3.0\n\nBINARY\nDATASET STRUCTURED_POINTS\n\
+        DIMENSIONS 4294967296 4294967296 4294967296\nSCALARS density float\n";
+    assert!(matches!(read_vtk(bytes), Err(VolumeError::Format(_))));
+
+    let bytes = b"# vtk DataFile Version 3.0\n\nASCII\nDATASET POLYDATA\n";
+    assert!(matches!(read_vtk(bytes), Err(VolumeError::Unsupported(_))));
+}
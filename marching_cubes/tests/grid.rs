@@ -0,0 +1,118 @@
+mod common;
+
+use common::assert_closed;
+use marching_cubes::formats::VolumeError;
+use marching_cubes::{polygonize, Blend, Bounds, Csg, Grid, Metaball, Samples, ScalarField, Sdf};
+
+#[test]
+fn meshes_in_world_units_on_anisotropic_grids() {
+    // Off the origin, with cells of a different size along each axis
+    let bounds = Bounds::new((9.2, -5.3, 5.9), (11.5, -3.1, 8.4));
+    let grid = Grid::new(bounds, 23, 11, 31);
+    let (sx, sy, sz) = grid.cell_size();
+    assert!((sx - 0.1).abs() < 1e-5 && (sy - 0.2).abs() < 1e-5 && (sz - 2.5 / 31.0).abs() < 1e-5);
+
+    let sphere = Sdf::sphere((10.3, -4.2, 7.1), 0.8);
+    let mesh = polygonize(&sphere, &grid, 0.0);
+    assert_eq!(assert_closed(&mesh, "sphere"), 2);
+    for vertex in mesh.vertices.chunks(3) {
+        for (axis, &coordinate) in vertex.iter().enumerate() {
+            let (min, max) = [
+                (bounds.min.0, bounds.max.0),
+                (bounds.min.1, bounds.max.1),
+                (bounds.min.2, bounds.max.2),
+            ][axis];
+            assert!(coordinate >= min && coordinate <= max);
+        }
+        // Within a fraction of the coarsest cell
+        assert!(sphere.value(vertex[0], vertex[1], vertex[2]).abs() < 0.02);
+    }
+
+    // Reaching all the way around the sphere
+    let extent = |axis: usize| {
+        let coordinates = mesh.vertices.iter().skip(axis).step_by(3);
+        let (low, high) = coordinates.fold((f32::MAX, f32::MIN), |(low, high), &c| {
+            (low.min(c), high.max(c))
+        });
+        high - low
+    };
+    for axis in 0..3 {
+        assert!(
+            (extent(axis) - 1.6).abs() < 0.05,
+            "axis {axis}: {}",
+            extent(axis)
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "at least one cell")]
+fn zero_cell_counts_are_rejected() {
+    Grid::new(Bounds::new((0.0, 0.0, 0.0), (1.0, 1.0, 1.0)), 4, 0, 4);
+}
+
+#[test]
+fn samples_check_their_length() {
+    let grid = Grid::from_points((0.0, 0.0, 0.0), (3, 2, 2), (1.0, 1.0, 1.0));
+    let samples = Samples::try_new(grid, vec![0.5; 12]).unwrap();
+    assert_eq!(samples, Samples::new(grid, vec![0.5; 12]));
+
+    for length in [0, 11, 13] {
+        let error = Samples::try_new(grid, vec![0.5; length]).unwrap_err();
+        assert!(matches!(error, VolumeError::Format(_)), "{error}");
+    }
+
+    // A lattice too large to address can't match any buffer
+    let huge = Grid::from_points((0.0, 0.0, 0.0), (usize::MAX, 2, 2), (1.0, 1.0, 1.0));
+    assert!(Samples::try_new(huge, Vec::new()).is_err());
+}
+
+#[test]
+fn fit_surrounds_the_field_with_a_margin() {
+    let sphere = Sdf::sphere((0.3, -0.2, 0.1), 0.5);
+    let grid = Grid::fit(&sphere, 20).unwrap();
+
+    // Cubic cells, 20 across the sphere and the margin
+    let (sx, sy, sz) = grid.cell_size();
+    assert!((sx - 0.05).abs() < 1e-4 && (sy - sx).abs() < 1e-4 && (sz - sx).abs() < 1e-4);
+    assert!(grid.nx >= 22 && grid.nx <= 23);
+    assert!((grid.bounds.min.0 + 0.25).abs() < 1e-4 && (grid.bounds.max.1 - 0.35).abs() < 1e-4);
+
+    // The surface on the bounds isn't clipped
+    let mesh = polygonize(&sphere, &grid, 0.0);
+    assert!(!mesh.indices.is_empty());
+    assert_eq!(assert_closed(&mesh, "sphere"), 2);
+}
+
+#[test]
+fn fit_covers_metaball_blends() {
+    let balls = [
+        Metaball::new(0.2, 0.5, 0.5, 0.05, 1.0),
+        Metaball::new(0.8, 0.5, 0.5, 0.05, 1.0),
+    ];
+    let grid = Grid::fit(&balls[..], 16).unwrap();
+    let bounds = balls[..].bounds().unwrap();
+    assert!(grid.bounds.min.0 < bounds.min.0 && grid.bounds.max.0 > bounds.max.0);
+
+    let mesh = polygonize(&balls[..], &grid, 0.5);
+    assert!(!mesh.indices.is_empty());
+    // Two spheres, the balls being too far apart to merge
+    assert_eq!(assert_closed(&mesh, "metaballs"), 4);
+}
+
+#[test]
+fn fit_needs_bounds() {
+    assert_eq!(Grid::fit(&Sdf::plane((0.0, 1.0, 0.0), 0.0), 16), None);
+    assert_eq!(Grid::fit(&Sdf::sphere((0.0, 0.0, 0.0), 1.0), 0), None);
+
+    // Intersecting shapes that don't overlap leaves nothing to mesh
+    let apart = Csg::intersection(
+        Sdf::sphere((-2.0, 0.0, 0.0), 0.5),
+        Sdf::sphere((2.0, 0.0, 0.0), 0.5),
+        Blend::Hard,
+    );
+    assert!(apart
+        .bounds()
+        .is_none_or(|bounds: Bounds| bounds.min.0 > bounds.max.0));
+    assert_eq!(Grid::fit(&apart, 16), None);
+}
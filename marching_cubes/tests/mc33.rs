@@ -0,0 +1,315 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::assert_closed;
+use marching_cubes::{polygonize_samples, Grid, GridData, MeshOptions, Mode, Samples};
+
+/// Xorshift, so the grids are the same on every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+fn mesh(points: (usize, usize, usize), values: Vec<f32>) -> GridData {
+    let grid = Grid::from_points((0.0, 0.0, 0.0), points, (1.0, 1.0, 1.0));
+    let options = MeshOptions {
+        closed: true,
+        mode: Mode::MarchingCubes33,
+    };
+    polygonize_samples(&Samples::new(grid, values), 0.5, &options)
+}
+
+#[test]
+fn every_corner_configuration_is_closed() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for signs in 0..256 {
+        // Random magnitudes move the face and interior saddles around,
+        // covering both resolutions of the ambiguous cases
+        for sample in 0..16 {
+            let values = (0..8)
+                .map(|corner| {
+                    let magnitude = 0.01 + 0.49 * rng.next();
+                    if signs >> corner & 1 == 1 {
+                        0.5 + magnitude
+                    } else {
+                        0.5 - magnitude
+                    }
+                })
+                .collect();
+            let mesh = mesh((2, 2, 2), values);
+            assert_closed(&mesh, &format!("signs {signs:#010b}, sample {sample}"));
+        }
+    }
+}
+
+#[test]
+fn ambiguous_faces_agree_between_cells() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for sample in 0..200 {
+        let values = (0..5 * 5 * 5).map(|_| rng.next()).collect();
+        let mesh = mesh((5, 5, 5), values);
+        assert_closed(&mesh, &format!("sample {sample}"));
+    }
+}
+
+#[test]
+fn exact_saddles_are_decided_consistently() {
+    // A face whose saddle sits exactly on the threshold, and one with
+    // infinite samples
+    for values in [
+        [1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0],
+        [
+            f32::INFINITY,
+            0.0,
+            f32::NEG_INFINITY,
+            1.0,
+            1.0,
+            0.0,
+            0.0,
+            1.0,
+        ],
+    ] {
+        let mesh = mesh((2, 2, 2), values.to_vec());
+        assert!(!mesh.indices.is_empty());
+        assert_closed(&mesh, &format!("{values:?}"));
+    }
+}
+
+/// The trilinear interpolant of `values` over the unit cell, sampled
+/// `resolution` steps apart along each axis, x fastest.
+fn sample_interpolant(values: &[f32; 8], resolution: usize) -> Vec<f32> {
+    let n = resolution + 1;
+    let lerp = |a: f32, b: f32, t: f32| a + t * (b - a);
+    (0..n * n * n)
+        .map(|i| {
+            let [x, y, z] = [i % n, i / n % n, i / (n * n)].map(|c| c as f32 / resolution as f32);
+            let face = |offset: usize| {
+                lerp(
+                    lerp(values[offset], values[offset + 1], x),
+                    lerp(values[offset + 2], values[offset + 3], x),
+                    y,
+                )
+            };
+            lerp(face(0), face(4), z)
+        })
+        .collect()
+}
+
+/// Connected parts of the inside and of the outside of the sampled cell,
+/// flood-filled along the lattice.
+fn regions(samples: &[f32], threshold: f32) -> (usize, usize) {
+    let n = (samples.len() as f32).cbrt().round() as usize;
+    let inside: Vec<bool> = samples.iter().map(|&value| value >= threshold).collect();
+    let mut seen = vec![false; inside.len()];
+    let mut counts = [0, 0];
+    for start in 0..inside.len() {
+        if seen[start] {
+            continue;
+        }
+        counts[inside[start] as usize] += 1;
+        seen[start] = true;
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            let (x, y, z) = (i % n, i / n % n, i / (n * n));
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < n).then(|| i + 1),
+                (y > 0).then(|| i - n),
+                (y + 1 < n).then(|| i + n),
+                (z > 0).then(|| i - n * n),
+                (z + 1 < n).then(|| i + n * n),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                if !seen[j] && inside[j] == inside[start] {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+    (counts[1], counts[0])
+}
+
+/// Representative of `i`'s set in a union-find forest.
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Connected pieces, boundary loops and Euler characteristic of an open
+/// mesh.
+fn surface_topology(mesh: &GridData) -> (usize, usize, i64) {
+    let count = mesh.vertices.len() / 3;
+    let mut edges = HashSet::new();
+    let mut pieces: Vec<usize> = (0..count).collect();
+    for triangle in mesh.indices.chunks(3) {
+        for k in 0..3 {
+            let (a, b) = (triangle[k] as usize, triangle[(k + 1) % 3] as usize);
+            edges.insert((a, b));
+            let (a, b) = (root(&mut pieces, a), root(&mut pieces, b));
+            pieces[a] = b;
+        }
+    }
+
+    let mut loops: Vec<usize> = (0..count).collect();
+    let mut on_boundary = HashSet::new();
+    for &(a, b) in &edges {
+        if !edges.contains(&(b, a)) {
+            on_boundary.extend([a, b]);
+            let (a, b) = (root(&mut loops, a), root(&mut loops, b));
+            loops[a] = b;
+        }
+    }
+
+    let used: HashSet<usize> = mesh.indices.iter().map(|&i| i as usize).collect();
+    let roots = |parents: &mut [usize], vertices: &HashSet<usize>| {
+        vertices
+            .iter()
+            .map(|&i| root(parents, i))
+            .collect::<HashSet<_>>()
+            .len()
+    };
+    let undirected = edges
+        .iter()
+        .filter(|&&(a, b)| a < b || !edges.contains(&(b, a)))
+        .count();
+    let euler = used.len() as i64 - undirected as i64 + mesh.indices.len() as i64 / 3;
+    (
+        roots(&mut pieces, &used),
+        roots(&mut loops, &on_boundary),
+        euler,
+    )
+}
+
+/// Meshes a single cell with the given corner values and checks its surface
+/// has the topology of the trilinear interpolant's: one piece between each
+/// pair of neighbouring regions, and no handles. Returns the number of
+/// pieces and of loops they are bounded by, or `None` if a saddle or corner
+/// lies within `margin` of the threshold, too close for the regions
+/// sampled `resolution` steps along each axis to be trusted.
+fn assert_interpolant_topology(
+    values: [f32; 8],
+    margin: f32,
+    resolution: usize,
+) -> Option<(usize, usize)> {
+    const FACES: [[usize; 4]; 6] = [
+        [0, 2, 6, 4],
+        [1, 3, 7, 5],
+        [0, 1, 5, 4],
+        [2, 3, 7, 6],
+        [0, 1, 3, 2],
+        [4, 5, 7, 6],
+    ];
+    let face_saddle = |face: [usize; 4]| {
+        let [a, b, c, d] = face.map(|corner| values[corner] - 0.5);
+        (a * b < 0.0 && b * c < 0.0 && c * d < 0.0).then(|| (a * c - b * d) / (a + c - b - d))
+    };
+    let near_critical = values.iter().any(|value| (value - 0.5).abs() < margin)
+        || FACES
+            .into_iter()
+            .filter_map(face_saddle)
+            .any(|saddle| saddle.abs() < margin);
+    if near_critical {
+        return None;
+    }
+    let samples = sample_interpolant(&values, resolution);
+    let sampled = regions(&samples, 0.5);
+    if regions(&samples, 0.5 - margin) != sampled || regions(&samples, 0.5 + margin) != sampled {
+        return None;
+    }
+
+    let grid = Grid::from_points((0.0, 0.0, 0.0), (2, 2, 2), (1.0, 1.0, 1.0));
+    let options = MeshOptions {
+        closed: false,
+        mode: Mode::MarchingCubes33,
+    };
+    let mesh = polygonize_samples(&Samples::new(grid, values.to_vec()), 0.5, &options);
+    let (pieces, loops, euler) = surface_topology(&mesh);
+
+    // The pieces cut the cell into the regions like edges of a tree, and
+    // each is a disk, a tube or another sphere with holes
+    let case = format!("{values:?}: {sampled:?} regions");
+    assert_eq!(pieces, sampled.0 + sampled.1 - 1, "{case}");
+    assert_eq!(euler, 2 * pieces as i64 - loops as i64, "{case}");
+    Some((pieces, loops))
+}
+
+/// Whether the corners in the `corners` bitmask fall apart along the cube's
+/// edges, leaving the faces or the interior to decide how they connect.
+fn split_along_edges(corners: u32) -> bool {
+    let Some(first) = (0..8).find(|&corner| corners >> corner & 1 == 1) else {
+        return false;
+    };
+    let mut reached = 1 << first;
+    let mut stack = vec![first];
+    while let Some(corner) = stack.pop() {
+        for axis in 0..3 {
+            let neighbour = corner ^ 1 << axis;
+            if corners >> neighbour & 1 == 1 && reached >> neighbour & 1 == 0 {
+                reached |= 1 << neighbour;
+                stack.push(neighbour);
+            }
+        }
+    }
+    reached != corners
+}
+
+#[test]
+fn topology_matches_the_trilinear_interpolant() {
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+    let (mut checked, mut tunnels) = (0, 0);
+    let ambiguous =
+        (0..256).filter(|&signs| split_along_edges(signs) || split_along_edges(!signs & 0xff));
+    for signs in ambiguous {
+        for _ in 0..8 {
+            // Tunnels need the corners on one side far from the threshold
+            // and the others close to it, so bias one side or neither
+            let strong = (rng.next() * 3.0) as u32;
+            let values = std::array::from_fn(|corner| {
+                let side = signs >> corner & 1;
+                let magnitude = match strong {
+                    2 => 0.02 + 0.48 * rng.next(),
+                    _ if side == strong => 0.2 + 0.3 * rng.next(),
+                    _ => 0.02 + 0.1 * rng.next(),
+                };
+                if side == 1 {
+                    0.5 + magnitude
+                } else {
+                    0.5 - magnitude
+                }
+            });
+
+            if let Some((pieces, loops)) = assert_interpolant_topology(values, 0.02, 12) {
+                checked += 1;
+                tunnels += (loops > pieces) as usize;
+            }
+        }
+    }
+    assert!(checked > 700, "only {checked} cells checked");
+    assert!(tunnels > 10, "only {tunnels} tunnels");
+}
+
+#[test]
+fn tunnels_join_two_of_three_loops() {
+    // Case 13: four corners inside, no two sharing an edge, with the faces
+    // leaving two groups on either side. The interior joins two of the
+    // groups on one side, leaving a tube and a disk.
+    for values in [
+        [0.863, 0.102, 0.098, 0.863, 0.057, 0.871, 0.948, 0.475],
+        [0.893, 0.157, 0.033, 0.855, 0.463, 0.775, 0.782, 0.178],
+        [0.021, 0.575, 0.887, 0.028, 0.921, 0.118, 0.178, 0.952],
+    ] {
+        let topology = assert_interpolant_topology(values, 0.004, 32);
+        assert_eq!(topology, Some((2, 3)), "{values:?}");
+    }
+}
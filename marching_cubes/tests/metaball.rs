@@ -0,0 +1,378 @@
+mod common;
+
+use common::assert_closed;
+use marching_cubes::{polygonize, Bounds, Grid, Kernel, Metaball, ScalarField};
+
+const KERNELS: [Kernel; 5] = [
+    Kernel::Hybrid,
+    Kernel::Blinn { blobbiness: 2.0 },
+    Kernel::Wyvill,
+    Kernel::Murakami,
+    Kernel::InverseSquare,
+];
+
+#[test]
+fn kernels_follow_their_formulas() {
+    // Reaching out to 0.5 from the center
+    let radius = 0.25;
+    for d in [0.1, 0.2, 0.3, 0.45] {
+        let s: f32 = d * d / radius;
+        for (kernel, expected) in [
+            (Kernel::Blinn { blobbiness: 2.0 }, (-2.0 * s).exp()),
+            (
+                Kernel::Wyvill,
+                1.0 - 22.0 / 9.0 * s + 17.0 / 9.0 * s * s - 4.0 / 9.0 * s * s * s,
+            ),
+            (Kernel::Murakami, (1.0 - s) * (1.0 - s)),
+            (Kernel::InverseSquare, 1.0 / s),
+        ] {
+            let ball = Metaball::with_kernel(0.0, 0.0, 0.0, radius, 2.0, kernel);
+            let value = ball.value(0.0, d, 0.0);
+            assert!(
+                (value - 2.0 * expected).abs() < 1e-5 * (1.0 + value.abs()),
+                "{kernel:?} at {d}: {value}"
+            );
+        }
+    }
+}
+
+#[test]
+fn finite_kernels_fade_out_at_their_reach() {
+    let radius = 0.25;
+    for kernel in [Kernel::Hybrid, Kernel::Wyvill, Kernel::Murakami] {
+        let ball = Metaball::with_kernel(0.0, 0.0, 0.0, radius, 1.0, kernel);
+
+        // Smoothly: nearly zero just inside, exactly zero from there on
+        assert!(ball.value(0.499, 0.0, 0.0).abs() < 1e-3, "{kernel:?}");
+        for d in [0.5, 0.6, 2.0] {
+            assert_eq!(ball.value(d, 0.0, 0.0), 0.0, "{kernel:?}");
+            assert_eq!(ball.gradient(d, 0.0, 0.0), Some((0.0, 0.0, 0.0)));
+        }
+
+        // And falling monotonically on the way
+        let values: Vec<f32> = (0..10)
+            .map(|i| ball.value(i as f32 * 0.05, 0.0, 0.0))
+            .collect();
+        assert!(
+            values.windows(2).all(|pair| pair[1] <= pair[0]),
+            "{kernel:?}"
+        );
+    }
+
+    let blinn = Metaball::with_kernel(
+        0.0,
+        0.0,
+        0.0,
+        radius,
+        1.0,
+        Kernel::Blinn { blobbiness: 2.0 },
+    );
+    let inverse = Metaball::with_kernel(0.0, 0.0, 0.0, radius, 1.0, Kernel::InverseSquare);
+    assert!(blinn.value(2.0, 0.0, 0.0) > 0.0);
+    assert!(inverse.value(2.0, 0.0, 0.0) > 0.0);
+}
+
+#[test]
+fn kernel_gradients_match_finite_differences() {
+    for kernel in KERNELS {
+        let ball = Metaball::with_kernel(0.1, -0.2, 0.3, 0.25, 1.5, kernel);
+        let points = [
+            (0.25, -0.2, 0.3),
+            (0.1, 0.05, 0.45),
+            (-0.15, -0.35, 0.2),
+            (0.35, 0.0, 0.1),
+        ];
+        let checked = common::assert_gradients(&ball, points, 0.01, &format!("{kernel:?}"));
+        assert_eq!(checked, 4, "{kernel:?}");
+    }
+
+    // Balls of different kernels blend by summing, gradients included
+    let balls: Vec<Metaball> = KERNELS
+        .iter()
+        .enumerate()
+        .map(|(i, &kernel)| Metaball::with_kernel(i as f32 * 0.2, 0.0, 0.0, 0.09, 1.0, kernel))
+        .collect();
+    let points = [(0.1, 0.05, 0.0), (0.5, -0.1, 0.1), (0.75, 0.1, -0.05)];
+    assert_eq!(
+        common::assert_gradients(&balls[..], points, 0.01, "blend"),
+        3
+    );
+}
+
+#[test]
+fn negative_balls_carve_holes() {
+    let balls = [
+        Metaball::new(0.5, 0.5, 0.5, 0.16, 1.0),
+        Metaball::negative(0.52, 0.48, 0.5, 0.02, 1.0),
+    ];
+    let threshold = 2.0;
+    assert!(balls[0].value(0.52, 0.48, 0.5) > threshold);
+    assert!(balls[..].value(0.52, 0.48, 0.5) < threshold);
+
+    // A sphere with a hollow inside: two closed surfaces
+    let mesh = polygonize(&balls[..], &Grid::unit_cube(40), threshold);
+    assert_eq!(assert_closed(&mesh, "carved"), 4);
+
+    // The cavity's normals point into it, away from the solid
+    let inner = mesh
+        .vertices
+        .chunks(3)
+        .zip(mesh.normals.chunks(3))
+        .filter(|(vertex, _)| {
+            (vertex[0] - 0.52).powi(2) + (vertex[1] - 0.48).powi(2) + (vertex[2] - 0.5).powi(2)
+                < 0.16 * 0.16
+        });
+    let mut count = 0;
+    for (vertex, normal) in inner {
+        let towards = normal[0] * (0.52 - vertex[0])
+            + normal[1] * (0.48 - vertex[1])
+            + normal[2] * (0.5 - vertex[2]);
+        assert!(towards > 0.0);
+        count += 1;
+    }
+    assert!(count > 0);
+}
+
+#[test]
+fn negative_balls_flip_value_and_gradient() {
+    for kernel in KERNELS {
+        let positive = Metaball::with_kernel(0.1, -0.2, 0.3, 0.25, 1.5, kernel);
+        let negative = Metaball {
+            negative: true,
+            ..positive
+        };
+        for (x, y, z) in [(0.25, -0.2, 0.3), (0.1, 0.05, 0.45), (-0.15, -0.35, 0.2)] {
+            assert_eq!(negative.value(x, y, z), -positive.value(x, y, z));
+            let (gx, gy, gz) = positive.gradient(x, y, z).unwrap();
+            assert_eq!(negative.gradient(x, y, z), Some((-gx, -gy, -gz)));
+        }
+    }
+
+    // And blend into the analytic gradient of the set
+    let balls = [
+        Metaball::new(0.0, 0.0, 0.0, 0.3, 1.0),
+        Metaball::negative(0.2, 0.1, 0.0, 0.1, 0.8),
+    ];
+    let checked = common::assert_gradients(&balls[..], common::points(0.6), 0.01, "carved");
+    assert!(checked > 400, "only {checked} smooth points");
+}
+
+#[test]
+fn bounds_leave_negative_balls_out() {
+    let positive = Metaball::new(0.5, 0.5, 0.5, 0.04, 1.0);
+    let far = Metaball::negative(3.0, 0.0, 0.0, 0.04, 1.0);
+    assert_eq!(
+        positive.bounds(),
+        Some(Bounds::new((0.3, 0.3, 0.3), (0.7, 0.7, 0.7)))
+    );
+    assert_eq!(far.bounds(), None);
+    assert_eq!([positive, far][..].bounds(), positive.bounds());
+    assert_eq!([far][..].bounds(), None);
+}
+
+#[test]
+fn normals_stay_finite_where_contributions_cancel() {
+    // On the lattice point halfway between two equal balls their gradients
+    // cancel exactly. Meshing at the field's value there puts a vertex right
+    // on the saddle.
+    let balls = [
+        Metaball::new(0.25, 0.5, 0.5, 0.09, 1.0),
+        Metaball::new(0.75, 0.5, 0.5, 0.09, 1.0),
+    ];
+    assert_eq!(balls[..].gradient(0.5, 0.5, 0.5), Some((0.0, 0.0, 0.0)));
+
+    let threshold = balls[..].value(0.5, 0.5, 0.5);
+    let mesh = polygonize(&balls[..], &Grid::unit_cube(20), threshold);
+    let saddle = mesh
+        .vertices
+        .chunks(3)
+        .position(|vertex| vertex == [0.5, 0.5, 0.5]);
+    assert!(saddle.is_some());
+    assert!(mesh.normals.iter().all(|component| component.is_finite()));
+}
+
+/// Stretched 2:1:0.5 along its local axes and turned a third of a turn
+/// around (1, 1, 1).
+fn ellipsoid(kernel: Kernel) -> Metaball {
+    let half = std::f32::consts::FRAC_PI_3;
+    let axis = half.sin() / 3f32.sqrt();
+    Metaball {
+        kernel,
+        ..Metaball::ellipsoid(
+            0.1,
+            -0.2,
+            0.05,
+            0.25,
+            1.5,
+            (2.0, 1.0, 0.5),
+            (axis, axis, axis, half.cos()),
+        )
+    }
+}
+
+#[test]
+fn ellipsoids_stretch_and_turn_the_ball() {
+    // The rotation takes x to y, y to z and z to x
+    for kernel in KERNELS {
+        let ball = ellipsoid(kernel);
+        let sphere = Metaball::with_kernel(0.0, 0.0, 0.0, 0.25, 1.5, kernel);
+        for d in [0.1, 0.3, 0.45] {
+            for (offset, along) in [
+                ((0.0, 2.0 * d, 0.0), "x"),
+                ((0.0, 0.0, d), "y"),
+                ((0.5 * d, 0.0, 0.0), "z"),
+            ] {
+                let value = ball.value(0.1 + offset.0, -0.2 + offset.1, 0.05 + offset.2);
+                let expected = sphere.value(d, 0.0, 0.0);
+                assert!(
+                    (value - expected).abs() < 1e-4 * (1.0 + expected.abs()),
+                    "{kernel:?} along local {along} at {d}: {value} vs {expected}"
+                );
+            }
+        }
+
+        let case = format!("ellipsoid {kernel:?}");
+        let points = common::points(0.6).map(|(x, y, z)| (x + 0.1, y - 0.2, z + 0.05));
+        let checked = common::assert_gradients(&ball, points, 0.01, &case);
+        assert!(checked > 400, "{case}: only {checked} smooth points");
+    }
+}
+
+#[test]
+fn ellipsoid_bounds_hold_the_surface() {
+    for kernel in [Kernel::Hybrid, Kernel::Wyvill, Kernel::Murakami] {
+        let ball = ellipsoid(kernel);
+        let bounds = ball.bounds().unwrap();
+        // Rows of the rotation are permuted axes, so the box is the scaled
+        // reach permuted: 0.5 wide along x, 2 along y and 1 along z
+        let extents = (
+            bounds.max.0 - bounds.min.0,
+            bounds.max.1 - bounds.min.1,
+            bounds.max.2 - bounds.min.2,
+        );
+        assert!((extents.0 - 0.5).abs() < 1e-5, "{kernel:?}: {extents:?}");
+        assert!((extents.1 - 2.0).abs() < 1e-5, "{kernel:?}: {extents:?}");
+        assert!((extents.2 - 1.0).abs() < 1e-5, "{kernel:?}: {extents:?}");
+
+        // Meshing close to zero, the surface reaches out most of the way to
+        // the edge of the ball's support
+        let grid = Grid::with_cell_size(bounds.expand(0.1), 0.02);
+        let mesh = polygonize(&ball, &grid, 0.01);
+        assert_eq!(assert_closed(&mesh, &format!("{kernel:?}")), 2);
+        let mut reach: (f32, f32, f32) = (0.0, 0.0, 0.0);
+        for vertex in mesh.vertices.chunks(3) {
+            assert!(vertex[0] >= bounds.min.0 && vertex[0] <= bounds.max.0);
+            assert!(vertex[1] >= bounds.min.1 && vertex[1] <= bounds.max.1);
+            assert!(vertex[2] >= bounds.min.2 && vertex[2] <= bounds.max.2);
+            reach.0 = reach.0.max((vertex[0] - 0.1).abs());
+            reach.1 = reach.1.max((vertex[1] + 0.2).abs());
+            reach.2 = reach.2.max((vertex[2] - 0.05).abs());
+        }
+        assert!(
+            reach.0 > 0.2 && reach.1 > 0.8 && reach.2 > 0.4,
+            "{kernel:?}: {reach:?}"
+        );
+    }
+}
+
+/// Asserts a skeleton ball's value at `point` is that of a point ball at
+/// `distance`, so the distance to the skeleton is measured right.
+fn assert_distance(ball: &Metaball, point: (f32, f32, f32), distance: f32, region: &str) {
+    let reference = Metaball::with_kernel(0.0, 0.0, 0.0, ball.radius, 1.0, ball.kernel);
+    let value = ball.value(point.0, point.1, point.2);
+    let expected = reference.value(distance, 0.0, 0.0);
+    assert!(
+        (value - expected).abs() < 1e-5,
+        "{region}: {value} vs {expected}"
+    );
+}
+
+#[test]
+fn triangles_measure_distance_to_every_region() {
+    let blinn = Kernel::Blinn { blobbiness: 2.0 };
+    let triangle = Metaball {
+        kernel: blinn,
+        ..Metaball::triangle((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0), 0.5, 1.0)
+    };
+
+    let regions = [
+        ("vertex a", (-0.1f32, -0.2, 0.05), (0.0, 0.0, 0.0)),
+        ("vertex b", (1.2, -0.1, 0.1), (1.0, 0.0, 0.0)),
+        ("vertex c", (-0.1, 1.3, 0.0), (0.0, 1.0, 0.0)),
+        ("edge ab", (0.4, -0.15, 0.1), (0.4, 0.0, 0.0)),
+        ("edge ac", (-0.2, 0.5, -0.1), (0.0, 0.5, 0.0)),
+        ("edge bc", (0.7, 0.7, 0.05), (0.5, 0.5, 0.0)),
+        ("face", (0.2, 0.3, 0.15), (0.2, 0.3, 0.0)),
+        ("face below", (0.1, 0.1, -0.2), (0.1, 0.1, 0.0)),
+    ];
+    for (region, point, closest) in regions {
+        let distance = ((point.0 - closest.0) * (point.0 - closest.0)
+            + (point.1 - closest.1) * (point.1 - closest.1)
+            + (point.2 - closest.2) * (point.2 - closest.2))
+            .sqrt();
+        assert_distance(&triangle, point, distance, region);
+    }
+
+    let points = regions.map(|(_, point, _)| point);
+    assert_eq!(
+        common::assert_gradients(&triangle, points, 0.01, "triangle"),
+        8
+    );
+}
+
+#[test]
+fn capsules_measure_distance_to_their_segment() {
+    let blinn = Kernel::Blinn { blobbiness: 2.0 };
+    let capsule = Metaball {
+        kernel: blinn,
+        ..Metaball::capsule((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), 0.5, 1.0)
+    };
+
+    let regions = [
+        ("before a", (-0.3, 0.1, 0.0), 0.1f32.hypot(0.3)),
+        ("along", (0.6, 0.2, -0.1), 0.2f32.hypot(0.1)),
+        ("past b", (1.2, 0.0, 0.15), 0.2f32.hypot(0.15)),
+    ];
+    for (region, point, distance) in regions {
+        assert_distance(&capsule, point, distance, region);
+    }
+    let points = regions.map(|(_, point, _)| point);
+    assert_eq!(
+        common::assert_gradients(&capsule, points, 0.01, "capsule"),
+        3
+    );
+}
+
+#[test]
+fn skeleton_bounds_cover_every_vertex() {
+    let close = |bounds: Option<Bounds>, min: (f32, f32, f32), max: (f32, f32, f32)| {
+        let bounds = bounds.unwrap();
+        [
+            (bounds.min.0, min.0),
+            (bounds.min.1, min.1),
+            (bounds.min.2, min.2),
+            (bounds.max.0, max.0),
+            (bounds.max.1, max.1),
+            (bounds.max.2, max.2),
+        ]
+        .iter()
+        .all(|(actual, expected)| (actual - expected).abs() < 1e-6)
+    };
+
+    // Reaching 0.1 around every point of the skeleton
+    let capsule = Metaball::capsule((0.2, 0.3, 0.4), (0.8, 0.5, 0.4), 0.01, 1.0);
+    assert!(close(capsule.bounds(), (0.1, 0.2, 0.3), (0.9, 0.6, 0.5)));
+    let triangle = Metaball::triangle((0.2, 0.3, 0.4), (0.8, 0.5, 0.4), (0.5, 0.9, 0.1), 0.01, 1.0);
+    assert!(close(triangle.bounds(), (0.1, 0.2, 0.0), (0.9, 1.0, 0.5)));
+
+    // A capsule meshes as one closed surface inside them
+    let capsule = Metaball::capsule((0.25, 0.4, 0.5), (0.75, 0.6, 0.5), 0.04, 1.0);
+    let bounds = capsule.bounds().unwrap();
+    let mesh = polygonize(&capsule, &Grid::unit_cube(40), 5.0);
+    assert_eq!(assert_closed(&mesh, "capsule"), 2);
+    for vertex in mesh.vertices.chunks(3) {
+        assert!(vertex[0] >= bounds.min.0 && vertex[0] <= bounds.max.0);
+        assert!(vertex[1] >= bounds.min.1 && vertex[1] <= bounds.max.1);
+        assert!(vertex[2] >= bounds.min.2 && vertex[2] <= bounds.max.2);
+    }
+}
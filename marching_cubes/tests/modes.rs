@@ -0,0 +1,131 @@
+mod common;
+
+use common::assert_closed;
+use marching_cubes::{polygonize_with, Bounds, Grid, MeshOptions, Mode, ScalarField, Sdf};
+
+fn domain() -> Grid {
+    Grid::new(Bounds::new((-1.0, -1.0, -1.0), (1.0, 1.0, 1.0)), 20, 20, 20)
+}
+
+/// Meshes a sphere inside the domain, open and closed, checking it comes
+/// out as a closed sphere with its vertices within `tolerance` of the
+/// surface and normals pointing out.
+fn assert_meshes_spheres(mode: Mode, tolerance: f32) {
+    let center = (0.03, -0.02, 0.01);
+    let sphere = Sdf::sphere(center, 0.61);
+    for closed in [false, true] {
+        let mesh = polygonize_with(&sphere, &domain(), 0.0, &MeshOptions { closed, mode });
+        assert_eq!(assert_closed(&mesh, &format!("{mode:?}")), 2, "{mode:?}");
+
+        for (vertex, normal) in mesh.vertices.chunks(3).zip(mesh.normals.chunks(3)) {
+            let distance = sphere.value(vertex[0], vertex[1], vertex[2]).abs();
+            assert!(distance < tolerance, "{mode:?}: vertex {distance} off");
+            let outwards = normal[0] * (vertex[0] - center.0)
+                + normal[1] * (vertex[1] - center.1)
+                + normal[2] * (vertex[2] - center.2);
+            assert!(outwards > 0.0, "{mode:?}: normal points in");
+        }
+    }
+}
+
+/// Meshes a sphere running out of the domain, checking it is capped where
+/// it leaves it, with every vertex off the sphere on the domain's faces.
+fn assert_caps_clipped_sphere(mode: Mode) {
+    let clipped = Sdf::sphere((0.3, 0.2, 0.1), 1.1);
+    let options = MeshOptions { closed: true, mode };
+    let mesh = polygonize_with(&clipped, &domain(), 0.0, &options);
+    assert_eq!(assert_closed(&mesh, &format!("clipped {mode:?}")), 2);
+
+    let mut capped = 0;
+    for vertex in mesh.vertices.chunks(3) {
+        // Up to rounding where the cap's plane is solved for
+        assert!(vertex
+            .iter()
+            .all(|coordinate| coordinate.abs() <= 1.0 + 1e-6));
+        if clipped.value(vertex[0], vertex[1], vertex[2]) > 0.01 {
+            let on_face = vertex
+                .iter()
+                .any(|coordinate| (coordinate.abs() - 1.0).abs() < 1e-6);
+            assert!(
+                on_face,
+                "{mode:?}: cap vertex {vertex:?} off the domain's faces"
+            );
+            capped += 1;
+        }
+    }
+    assert!(capped > 0, "{mode:?}: no cap");
+}
+
+#[test]
+fn marching_cubes_meshes_spheres() {
+    // Interpolating along lattice edges is off by a fraction of a cell
+    assert_meshes_spheres(Mode::MarchingCubes, 0.01);
+    assert_caps_clipped_sphere(Mode::MarchingCubes);
+}
+
+#[test]
+fn marching_tetrahedra_meshes_spheres() {
+    // Interpolating along lattice edges is off by a fraction of a cell
+    assert_meshes_spheres(Mode::MarchingTetrahedra, 0.01);
+    assert_caps_clipped_sphere(Mode::MarchingTetrahedra);
+}
+
+#[test]
+fn marching_cubes_33_meshes_spheres() {
+    // Interpolating along lattice edges is off by a fraction of a cell
+    assert_meshes_spheres(Mode::MarchingCubes33, 0.01);
+    assert_caps_clipped_sphere(Mode::MarchingCubes33);
+}
+
+#[test]
+fn surface_nets_meshes_spheres() {
+    // Averaging a cell's crossings pulls its vertex slightly inside
+    assert_meshes_spheres(Mode::SurfaceNets, 0.01);
+    assert_caps_clipped_sphere(Mode::SurfaceNets);
+}
+
+#[test]
+fn dual_contouring_meshes_spheres_and_sharp_corners() {
+    let mode = Mode::DualContouring {
+        singular_value_threshold: 0.1,
+    };
+    assert_meshes_spheres(mode, 0.01);
+    assert_caps_clipped_sphere(mode);
+
+    // Off the lattice, so each corner falls inside a cell
+    let center = (0.033, -0.021, 0.012);
+    let half = (0.551, 0.362, 0.633);
+    let cuboid = Sdf::cuboid(center, half);
+    let options = MeshOptions {
+        closed: false,
+        mode,
+    };
+    let mesh = polygonize_with(&cuboid, &domain(), 0.0, &options);
+    assert_eq!(assert_closed(&mesh, "cuboid"), 2);
+    for vertex in mesh.vertices.chunks(3) {
+        let distance = cuboid.value(vertex[0], vertex[1], vertex[2]).abs();
+        assert!(distance < 0.01, "vertex {distance} off the cuboid");
+    }
+    for corner in 0..8 {
+        let sign = |bit: usize| if corner & bit == 0 { -1.0 } else { 1.0 };
+        let corner = (
+            center.0 + sign(1) * half.0,
+            center.1 + sign(2) * half.1,
+            center.2 + sign(4) * half.2,
+        );
+        let hit = mesh.vertices.chunks(3).any(|vertex| {
+            (vertex[0] - corner.0).abs() < 1e-3
+                && (vertex[1] - corner.1).abs() < 1e-3
+                && (vertex[2] - corner.2).abs() < 1e-3
+        });
+        assert!(hit, "corner {corner:?} rounded off");
+    }
+}
+
+#[test]
+fn extended_marching_cubes_meshes_spheres() {
+    // Smooth cells are refined onto the surface
+    let mode = Mode::ExtendedMarchingCubes { feature_angle: 0.5 };
+    assert_meshes_spheres(mode, 1e-4);
+    assert_caps_clipped_sphere(mode);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::points;
+use marching_cubes::{Bend, Displace, Mirror, Repeat, ScalarField, Sdf, Transform, Twist};
+
+fn torus() -> Sdf {
+    Sdf::torus((0.1, 0.2, -0.05), 0.5, 0.2)
+}
+
+/// Asserts the analytic gradient agrees with central differences wherever
+/// the field is smooth, creases like the mirror planes or the torus' core
+/// aside.
+fn assert_gradients<F: ScalarField>(field: &F, case: &str) {
+    let checked = common::assert_gradients(field, points(0.8), 0.01, case);
+    assert!(checked > 400, "{case}: only {checked} smooth points");
+}
+
+#[test]
+fn gradients_match_finite_differences() {
+    // Rotated around (1, 1, 1), squashed and moved
+    let rotation = (0.3, 0.3, 0.3, 0.854_4);
+    assert_gradients(
+        &Transform::new(torus(), (0.1, -0.2, 0.3), rotation, (1.0, 0.5, 1.5)),
+        "transform",
+    );
+    assert_gradients(&Repeat::new(torus(), (1.3, 0.0, 1.1), None), "repeat");
+    assert_gradients(&Mirror::new(torus(), (true, false, true)), "mirror");
+    assert_gradients(&Twist::new(torus(), 1.5), "twist");
+    assert_gradients(&Bend::new(torus(), 0.8), "bend");
+    assert_gradients(
+        &Displace::new(torus(), Sdf::sphere((0.3, 0.0, 0.0), 0.4), 0.3),
+        "displace",
+    );
+}
+
+#[test]
+fn modifiers_move_the_field_as_described() {
+    let field = torus();
+    let transform = Transform::new(
+        field,
+        (1.0, 2.0, 3.0),
+        (0.0, 0.0, 0.0, 1.0),
+        (2.0, 2.0, 2.0),
+    );
+    let repeat = Repeat::new(field, (1.3, 0.0, 0.0), Some((1, 0, 0)));
+    let mirror = Mirror::new(field, (true, false, false));
+    let displace = Displace::new(field, Sdf::sphere((0.0, 0.0, 0.0), 1.0), 0.5);
+
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+    for (x, y, z) in points(0.8) {
+        let value = field.value(x, y, z);
+        let moved = transform.value(2.0 * x + 1.0, 2.0 * y + 2.0, 2.0 * z + 3.0);
+        assert!(close(moved, value));
+        assert_eq!(Twist::new(field, 0.0).value(x, y, z), value);
+        assert_eq!(Bend::new(field, 0.0).value(x, y, z), value);
+
+        // One copy on either side, and beyond that the last copy stretches
+        // on
+        if x.abs() < 0.65 {
+            assert!(close(repeat.value(x + 1.3, y, z), value));
+            assert!(close(repeat.value(x - 1.3, y, z), value));
+            assert!(close(
+                repeat.value(x + 2.6, y, z),
+                field.value(x + 1.3, y, z)
+            ));
+        }
+
+        assert_eq!(mirror.value(-x.abs(), y, z), field.value(x.abs(), y, z));
+        assert_eq!(mirror.value(x.abs(), y, z), field.value(x.abs(), y, z));
+
+        let sphere = Sdf::sphere((0.0, 0.0, 0.0), 1.0).value(x, y, z);
+        assert!(close(displace.value(x, y, z), value + 0.5 * sphere));
+    }
+}
+
+#[test]
+fn transforms_that_collapse_space_are_rejected() {
+    let identity = (0.0, 0.0, 0.0, 1.0);
+    let origin = (0.0, 0.0, 0.0);
+    for scale in [(0.0, 1.0, 1.0), (1.0, 1.0, 0.0), (1.0, f32::NAN, 1.0)] {
+        assert!(Transform::try_new(torus(), origin, identity, scale).is_none());
+    }
+    // Nor can a matrix flattening space onto a plane be undone
+    let flat = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]];
+    assert!(Transform::try_from_matrix(torus(), flat, origin).is_none());
+
+    let mirror = [[-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let mirrored = Transform::try_from_matrix(torus(), mirror, origin).unwrap();
+    assert_eq!(
+        mirrored.value(-0.6, 0.2, -0.05),
+        torus().value(0.6, 0.2, -0.05)
+    );
+}
+
+#[test]
+#[should_panic(expected = "invertible")]
+fn zero_scales_panic() {
+    Transform::new(
+        torus(),
+        (0.0, 0.0, 0.0),
+        (0.0, 0.0, 0.0, 1.0),
+        (1.0, 0.0, 1.0),
+    );
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::points;
+use marching_cubes::{Basis, Fractal, Noise, ScalarField};
+
+const BASES: [Basis; 4] = [Basis::Perlin, Basis::Simplex, Basis::Value, Basis::Worley];
+
+#[test]
+fn same_seed_same_noise() {
+    for basis in BASES {
+        for fractal in [Fractal::Fbm, Fractal::Ridged, Fractal::Turbulence] {
+            let a = Noise::fractal(basis, fractal, 42, 1.7, 4, 2.0, 0.5);
+            let b = Noise::fractal(basis, fractal, 42, 1.7, 4, 2.0, 0.5);
+            let other = Noise::fractal(basis, fractal, 43, 1.7, 4, 2.0, 0.5);
+
+            let mut differs = 0;
+            for (x, y, z) in points(2.5) {
+                assert_eq!(a.sample(x, y, z), b.sample(x, y, z), "{basis:?}");
+                if a.value(x, y, z) != other.value(x, y, z) {
+                    differs += 1;
+                }
+            }
+            assert!(differs > 450, "{basis:?} {fractal:?}: seeds barely matter");
+        }
+    }
+}
+
+#[test]
+fn noise_is_pinned_across_releases() {
+    // Regenerating these means every saved seed changes its terrain
+    let expected = [
+        (Basis::Perlin, -0.178_472_58),
+        (Basis::Simplex, -0.030_750_483),
+        (Basis::Value, 0.429_005_35),
+        (Basis::Worley, 0.313_404_32),
+    ];
+    for (basis, value) in expected {
+        let noise = Noise::new(basis, 7, 1.0);
+        let actual = noise.value(0.3, 1.7, -2.2);
+        assert!((actual - value).abs() < 1e-6, "{basis:?}: {actual}");
+    }
+}
+
+#[test]
+fn single_octaves_stay_in_range() {
+    for basis in BASES {
+        let noise = Noise::new(basis, 3, 1.3);
+        let (mut low, mut high) = (f32::MAX, f32::MIN);
+        for (x, y, z) in points(2.5) {
+            let value = noise.value(x, y, z);
+            low = low.min(value);
+            high = high.max(value);
+        }
+
+        assert!(low >= -1.0 && high <= 1.0, "{basis:?}: {low}..{high}");
+        // Not stuck at a constant
+        assert!(high - low > 0.3, "{basis:?}: {low}..{high}");
+        if basis == Basis::Worley {
+            assert!(low >= 0.0);
+        }
+    }
+}
+
+#[test]
+fn gradients_match_finite_differences() {
+    for basis in BASES {
+        for fractal in [Fractal::Fbm, Fractal::Ridged, Fractal::Turbulence] {
+            let noise = Noise::fractal(basis, fractal, 11, 0.9, 3, 2.0, 0.5);
+            // Worley cell borders, ridges and turbulence creases aside
+            let case = format!("{basis:?} {fractal:?}");
+            let checked = common::assert_gradients(&noise, points(2.5), 0.02, &case);
+            assert!(
+                checked > 350,
+                "{basis:?} {fractal:?}: only {checked} smooth points"
+            );
+        }
+    }
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use common::points;
+use marching_cubes::{ScalarField, Sdf};
+
+/// Asserts the signed distances at `expected` points, and the field as its
+/// negation.
+fn assert_distances(sdf: &Sdf, expected: &[((f32, f32, f32), f32)]) {
+    for &((x, y, z), distance) in expected {
+        let actual = sdf.distance(x, y, z);
+        assert!(
+            (actual - distance).abs() < 1e-4,
+            "{sdf:?} at {:?}: {actual} vs {distance}",
+            (x, y, z)
+        );
+        assert_eq!(sdf.value(x, y, z), -actual);
+    }
+}
+
+/// Asserts the closed-form gradient agrees with central differences
+/// around the primitive, away from its creases.
+fn assert_gradients(sdf: &Sdf) {
+    let case = format!("{sdf:?}");
+    let checked = common::assert_gradients(sdf, points(1.2), 0.01, &case);
+    assert!(checked > 400, "{case}: only {checked} smooth points");
+}
+
+#[test]
+fn sphere() {
+    let sphere = Sdf::sphere((0.1, 0.2, 0.3), 0.5);
+    assert_distances(
+        &sphere,
+        &[
+            ((0.1, 0.2, 1.3), 0.5),
+            ((0.4, 0.6, 0.3), 0.0),
+            ((0.1, 0.2, 0.3), -0.5),
+        ],
+    );
+    assert_gradients(&sphere);
+}
+
+#[test]
+fn cuboid() {
+    let cuboid = Sdf::cuboid((0.0, 0.0, 0.0), (0.5, 0.3, 0.2));
+    assert_distances(
+        &cuboid,
+        &[
+            // Off a face, an edge and a corner
+            ((1.0, 0.0, 0.0), 0.5),
+            ((1.0, 0.7, 0.0), 0.41f32.sqrt()),
+            ((0.8, 0.7, 0.6), 0.41f32.sqrt()),
+            ((0.4, 0.0, 0.0), -0.1),
+            ((0.0, 0.0, 0.0), -0.2),
+        ],
+    );
+    assert_gradients(&cuboid);
+}
+
+#[test]
+fn round_box() {
+    let round_box = Sdf::round_box((0.0, 0.0, 0.0), (0.5, 0.3, 0.2), 0.1);
+    assert_distances(
+        &round_box,
+        &[
+            ((1.0, 0.0, 0.0), 0.5),
+            // Off the rounded corner, centered on (0.4, 0.2, 0.1)
+            ((0.8, 0.6, 0.5), 0.48f32.sqrt() - 0.1),
+            ((0.0, 0.0, 0.0), -0.2),
+        ],
+    );
+    assert_gradients(&round_box);
+}
+
+#[test]
+fn torus() {
+    let torus = Sdf::torus((0.0, 0.0, 0.0), 0.5, 0.1);
+    assert_distances(
+        &torus,
+        &[
+            ((0.5, 0.0, 0.0), -0.1),
+            ((0.8, 0.0, 0.0), 0.2),
+            ((0.0, 0.3, 0.5), 0.2),
+            ((0.0, 0.0, 0.0), 0.4),
+        ],
+    );
+    assert_gradients(&torus);
+}
+
+#[test]
+fn capsule() {
+    let capsule = Sdf::capsule((0.0, 0.0, 0.0), (0.0, 1.0, 0.0), 0.2);
+    assert_distances(
+        &capsule,
+        &[
+            ((0.5, 0.5, 0.0), 0.3),
+            ((0.0, -0.5, 0.0), 0.3),
+            ((0.0, 1.2, 0.1), 0.05f32.sqrt() - 0.2),
+            ((0.0, 0.5, 0.0), -0.2),
+        ],
+    );
+    assert_gradients(&capsule);
+}
+
+#[test]
+fn cylinder() {
+    let cylinder = Sdf::cylinder((0.0, 0.0, 0.0), 0.3, 0.5);
+    assert_distances(
+        &cylinder,
+        &[
+            ((0.5, 0.0, 0.0), 0.2),
+            ((0.0, 0.8, 0.0), 0.3),
+            // Off the rim
+            ((0.7, 0.8, 0.0), 0.5),
+            ((0.0, 0.0, 0.0), -0.3),
+        ],
+    );
+    assert_gradients(&cylinder);
+}
+
+#[test]
+fn cone() {
+    // From a radius of 0.4 at y = -0.5 to a tip at y = 0.5
+    let cone = Sdf::cone((0.0, 0.0, 0.0), 0.5, 0.4, 0.0);
+    let slant = (-0.4f32, 1.0f32);
+    let slant_length = slant.0.hypot(slant.1);
+    let outward = (slant.1 / slant_length, -slant.0 / slant_length);
+    assert_distances(
+        &cone,
+        &[
+            ((0.0, -1.0, 0.0), 0.5),
+            ((0.0, 1.0, 0.0), 0.5),
+            ((0.6, -0.5, 0.0), 0.2),
+            // Off the middle of the slanted side
+            ((0.2 + 0.1 * outward.0, 0.1 * outward.1, 0.0), 0.1),
+            // The side is 0.2 / |slant| from the center, the base further
+            ((0.0, 0.0, 0.0), -0.2 / slant_length),
+        ],
+    );
+    assert_gradients(&cone);
+
+    // A truncated one too
+    assert_gradients(&Sdf::cone((0.1, 0.0, -0.1), 0.4, 0.5, 0.2));
+}
+
+#[test]
+fn plane() {
+    let plane = Sdf::plane((0.0, 2.0, 0.0), -1.0);
+    assert_distances(
+        &plane,
+        &[
+            ((0.3, 1.5, -0.2), 1.0),
+            ((0.0, 0.5, 0.0), 0.0),
+            ((0.0, 0.0, 0.0), -0.5),
+        ],
+    );
+    assert_gradients(&plane);
+}
+
+#[test]
+fn ellipsoid() {
+    let ellipsoid = Sdf::ellipsoid((0.0, 0.0, 0.0), (0.5, 0.3, 0.2));
+    // Exact along the axes, only a bound elsewhere
+    assert_distances(
+        &ellipsoid,
+        &[
+            ((1.0, 0.0, 0.0), 0.5),
+            ((0.0, 0.6, 0.0), 0.3),
+            ((0.0, 0.0, 0.3), 0.1),
+            ((0.5, 0.0, 0.0), 0.0),
+            ((0.0, 0.0, 0.0), -0.2),
+        ],
+    );
+    let off_axis = ellipsoid.distance(0.4, 0.3, 0.2);
+    assert!(off_axis > 0.0 && off_axis < 0.5);
+    assert_gradients(&ellipsoid);
+}
+
+#[test]
+fn hex_prism() {
+    // Side faces face ±z and every 60° from there, corners point along x
+    let prism = Sdf::hex_prism((0.0, 0.0, 0.0), 0.4, 0.3);
+    let corner = 0.4 / 0.75f32.sqrt();
+    assert_distances(
+        &prism,
+        &[
+            ((0.0, 0.0, 0.6), 0.2),
+            ((0.6 * 0.75f32.sqrt(), 0.0, -0.3), 0.2),
+            ((0.6, 0.0, 0.0), 0.6 - corner),
+            ((0.0, 0.5, 0.0), 0.2),
+            ((0.0, 0.0, 0.0), -0.3),
+        ],
+    );
+    assert_gradients(&prism);
+}
@@ -0,0 +1,198 @@
+use marching_cubes::formats::{Endian, VolumeError};
+use marching_cubes::{finite_difference, Heightmap, Interpolation, ScalarField, Terrain};
+
+/// 4x3 heights with some curvature, so bicubic differs from bilinear.
+fn heightmap() -> Heightmap {
+    Heightmap::new(
+        4,
+        3,
+        vec![
+            0.0, 0.2, 0.1, 0.5, //
+            0.3, 0.9, 0.4, 0.2, //
+            0.1, 0.6, 0.8, 0.7,
+        ],
+    )
+}
+
+#[test]
+fn both_interpolations_pass_through_the_samples() {
+    let map = heightmap();
+    for interpolation in [Interpolation::Bilinear, Interpolation::Bicubic] {
+        for row in 0..3 {
+            for column in 0..4 {
+                let (height, _) = map.sample(column as f32, row as f32, interpolation);
+                assert!((height - map.heights[column + 4 * row]).abs() < 1e-6);
+            }
+        }
+
+        // Clamped beyond the edges
+        assert_eq!(
+            map.sample(-2.0, 0.0, interpolation).0,
+            map.sample(0.0, 0.0, interpolation).0
+        );
+        assert_eq!(
+            map.sample(3.0, 7.0, interpolation).0,
+            map.sample(3.0, 2.0, interpolation).0
+        );
+    }
+}
+
+#[test]
+fn bilinear_blends_its_four_neighbours() {
+    let map = heightmap();
+    let (height, (du, dv)) = map.sample(1.25, 0.5, Interpolation::Bilinear);
+    let top = 0.75 * 0.2 + 0.25 * 0.1;
+    let bottom = 0.75 * 0.9 + 0.25 * 0.4;
+    assert!((height - 0.5 * (top + bottom)).abs() < 1e-6);
+    assert!((du - 0.5 * ((0.1 - 0.2) + (0.4 - 0.9))).abs() < 1e-6);
+    assert!((dv - (bottom - top)).abs() < 1e-6);
+}
+
+#[test]
+fn bicubic_reproduces_ramps_and_is_smooth() {
+    // Catmull-Rom is exact on linear data away from the clamped edges
+    let ramp = Heightmap::new(
+        5,
+        5,
+        (0..25)
+            .map(|i| 0.1 * (i % 5) as f32 + 0.05 * (i / 5) as f32)
+            .collect(),
+    );
+    let (height, (du, dv)) = ramp.sample(2.3, 1.6, Interpolation::Bicubic);
+    assert!((height - (0.23 + 0.08)).abs() < 1e-5);
+    assert!((du - 0.1).abs() < 1e-5 && (dv - 0.05).abs() < 1e-5);
+
+    // Slopes are the derivatives of the heights, and continuous across
+    // samples
+    let map = heightmap();
+    let delta = 1e-3;
+    for (u, v) in [(0.7, 0.4), (1.5, 1.2), (2.2, 0.9), (1.0 + 1e-4, 1.3)] {
+        let (_, (du, dv)) = map.sample(u, v, Interpolation::Bicubic);
+        let height = |u: f32, v: f32| map.sample(u, v, Interpolation::Bicubic).0;
+        let numeric_u = (height(u + delta, v) - height(u - delta, v)) / (2.0 * delta);
+        let numeric_v = (height(u, v + delta) - height(u, v - delta)) / (2.0 * delta);
+        assert!((du - numeric_u).abs() < 0.01, "{du} vs {numeric_u}");
+        assert!((dv - numeric_v).abs() < 0.01, "{dv} vs {numeric_v}");
+    }
+    let left = map.sample(1.0 - 1e-4, 1.0, Interpolation::Bicubic).1;
+    let right = map.sample(1.0 + 1e-4, 1.0, Interpolation::Bicubic).1;
+    assert!((left.0 - right.0).abs() < 0.01);
+}
+
+#[test]
+fn terrain_is_solid_below_the_ground() {
+    let mut terrain = Terrain::new(heightmap(), (3.0, 2.0, 2.0));
+    terrain.origin = (1.0, -0.5, 0.0);
+    terrain.interpolation = Interpolation::Bicubic;
+
+    // Sample (1, 1) at height 0.9 sits at (2, -0.5 + 1.8, 1)
+    assert!(terrain.value(2.0, 1.3, 1.0).abs() < 1e-6);
+    assert!(terrain.value(2.0, 1.0, 1.0) > 0.0);
+    assert!(terrain.value(2.0, 1.6, 1.0) < 0.0);
+
+    for point in [(1.7, 0.2, 0.4), (2.6, 0.9, 1.3), (3.3, 0.0, 1.7)] {
+        let analytic = terrain.gradient(point.0, point.1, point.2).unwrap();
+        let numeric = finite_difference(&terrain, point.0, point.1, point.2);
+        let error = (analytic.0 - numeric.0).abs()
+            + (analytic.1 - numeric.1).abs()
+            + (analytic.2 - numeric.2).abs();
+        assert!(error < 0.02, "{analytic:?} vs {numeric:?}");
+    }
+
+    let bounds = terrain.bounds().unwrap();
+    assert_eq!((bounds.min.0, bounds.max.0), (1.0, 4.0));
+    assert!(bounds.min.1 < -0.5 && bounds.max.1 > -0.5 + 1.8);
+}
+
+#[test]
+fn bounds_hold_the_bicubic_overshoot() {
+    // The weights at the middle of the center cell are -1/16 and 9/16 per
+    // axis, so raising the samples whose 2D weights are positive overshoots
+    // the range by 0.28
+    let outer = |i: usize| i == 0 || i == 3;
+    let heights = (0..16)
+        .map(|i| {
+            if outer(i % 4) == outer(i / 4) {
+                1.0
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let mut terrain = Terrain::new(Heightmap::new(4, 4, heights), (3.0, 1.0, 3.0));
+    terrain.interpolation = Interpolation::Bicubic;
+
+    let bounds = terrain.bounds().unwrap();
+    let mut peak = f32::MIN;
+    for i in 0..=60 {
+        for j in 0..=60 {
+            let (u, v) = (i as f32 * 0.05, j as f32 * 0.05);
+            let (height, _) = terrain.heightmap.sample(u, v, Interpolation::Bicubic);
+            assert!(height > bounds.min.1 && height < bounds.max.1, "{height}");
+            peak = peak.max(height);
+        }
+    }
+    assert!(peak > 1.28, "{peak}");
+}
+
+#[test]
+fn reads_pgm_and_raw_heights() {
+    let map = Heightmap::from_pgm(b"P5\n# comment\n2 2\n255\n\x00\x33\x66\xff").unwrap();
+    assert_eq!((map.columns, map.rows), (2, 2));
+    assert_eq!(map.heights, [0.0, 0.2, 0.4, 1.0]);
+
+    let map = Heightmap::from_pgm(b"P5 2 1 1000\n\x00\x64\x03\xe8").unwrap();
+    assert_eq!(map.heights, [0.1, 1.0]);
+
+    let map = Heightmap::from_pgm(b"P2\n3 1\n4\n0 2 4\n").unwrap();
+    assert_eq!(map.heights, [0.0, 0.5, 1.0]);
+
+    let bytes = [0x00, 0x00, 0xff, 0xff];
+    let little = Heightmap::from_raw16(&bytes, 2, 1, Endian::Little).unwrap();
+    assert_eq!(little.heights, [0.0, 1.0]);
+    let bytes = [0x80, 0x00, 0x00, 0x00];
+    let big = Heightmap::from_raw16(&bytes, 1, 2, Endian::Big).unwrap();
+    assert!((big.heights[0] - 32768.0 / 65535.0).abs() < 1e-6);
+}
+
+#[test]
+fn rejects_bad_heightmaps() {
+    assert_eq!(
+        Heightmap::try_new(2, 2, vec![0.0, 0.1, 0.2, 0.3]).unwrap(),
+        Heightmap::new(2, 2, vec![0.0, 0.1, 0.2, 0.3])
+    );
+    for (columns, rows, length) in [(2, 2, 3), (2, 2, 5), (usize::MAX, 2, 0)] {
+        assert!(matches!(
+            Heightmap::try_new(columns, rows, vec![0.0; length]),
+            Err(VolumeError::Format(_))
+        ));
+    }
+    assert!(matches!(
+        Heightmap::from_pgm(b"P5\n2 2\n255\n\x00\x01"),
+        Err(VolumeError::Truncated { .. })
+    ));
+    assert!(matches!(
+        Heightmap::from_pgm(b"P2\n2 2\n255\n1 2 3"),
+        Err(VolumeError::Format(_))
+    ));
+    assert!(matches!(
+        Heightmap::from_pgm(b"P5\n2 2\n0\n"),
+        Err(VolumeError::Format(_))
+    ));
+    assert!(matches!(
+        Heightmap::from_pgm(b"P6\n1 1\n255\n\x00\x00\x00"),
+        Err(VolumeError::Unsupported(_))
+    ));
+    assert!(matches!(
+        Heightmap::from_pgm(format!("P5\n{} 3\n255\n", usize::MAX).as_bytes()),
+        Err(VolumeError::Format(_))
+    ));
+    assert!(matches!(
+        Heightmap::from_raw16(&[0; 6], 2, 2, Endian::Little),
+        Err(VolumeError::Truncated { .. })
+    ));
+    assert!(matches!(
+        Heightmap::from_raw16(&[], usize::MAX / 2, 2, Endian::Little),
+        Err(VolumeError::Format(_))
+    ));
+}
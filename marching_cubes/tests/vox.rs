@@ -0,0 +1,163 @@
+use marching_cubes::formats::{read_vox, VolumeError, VoxelVolume};
+use marching_cubes::{polygonize_with, MeshOptions, ScalarField};
+
+/// A chunk with its content and children.
+fn chunk(id: &[u8; 4], content: &[u8], children: &[u8]) -> Vec<u8> {
+    let mut bytes = id.to_vec();
+    bytes.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(children.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(content);
+    bytes.extend_from_slice(children);
+    bytes
+}
+
+fn vox(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = b"VOX ".to_vec();
+    bytes.extend_from_slice(&150u32.to_le_bytes());
+    bytes.extend_from_slice(&chunk(b"MAIN", &[], &chunks.concat()));
+    bytes
+}
+
+fn size(x: u32, y: u32, z: u32) -> Vec<u8> {
+    let content: Vec<u8> = [x, y, z].iter().flat_map(|n| n.to_le_bytes()).collect();
+    chunk(b"SIZE", &content, &[])
+}
+
+fn xyzi(voxels: &[[u8; 4]]) -> Vec<u8> {
+    let mut content = (voxels.len() as u32).to_le_bytes().to_vec();
+    content.extend(voxels.iter().flatten());
+    chunk(b"XYZI", &content, &[])
+}
+
+#[test]
+fn reads_models_and_palette() {
+    let mut palette = vec![0u8; 1024];
+    palette[0..4].copy_from_slice(&[10, 20, 30, 255]);
+    let bytes = vox(&[
+        size(2, 1, 1),
+        xyzi(&[[0, 0, 0, 1], [1, 0, 0, 1]]),
+        size(1, 1, 3),
+        xyzi(&[[0, 0, 2, 7]]),
+        chunk(b"RGBA", &palette, &[]),
+    ]);
+
+    let vox = read_vox(&bytes).unwrap();
+    assert_eq!(vox.models.len(), 2);
+    assert_eq!(vox.models[0].size, (2, 1, 1));
+    assert_eq!(vox.models[0].voxels, [(0, 0, 0, 1), (1, 0, 0, 1)]);
+    assert_eq!(vox.models[1].size, (1, 1, 3));
+    assert_eq!(vox.models[1].voxels, [(0, 0, 2, 7)]);
+    // Color index i is stored in entry i - 1
+    assert_eq!(vox.palette[1], (10, 20, 30, 255));
+}
+
+#[test]
+fn falls_back_on_the_default_palette() {
+    let vox = read_vox(&vox(&[size(1, 1, 1), xyzi(&[[0, 0, 0, 1]])])).unwrap();
+    assert_eq!(vox.palette[1], (255, 255, 255, 255));
+    assert_eq!(vox.palette[255], (0x11, 0x11, 0x11, 255));
+}
+
+#[test]
+fn rejects_malformed_files() {
+    assert!(matches!(
+        read_vox(b"RIFF\0\0\0\0"),
+        Err(VolumeError::Format(_))
+    ));
+    assert!(matches!(
+        read_vox(&vox(&[xyzi(&[[0, 0, 0, 1]])])),
+        Err(VolumeError::Format(_))
+    ));
+    assert!(matches!(
+        read_vox(&vox(&[size(1000, 1, 1)])),
+        Err(VolumeError::Format(_))
+    ));
+
+    // Chunk and voxel counts promising more than the file holds
+    let mut bytes = vox(&[size(1, 1, 1), xyzi(&[[0, 0, 0, 1]])]);
+    bytes.truncate(bytes.len() - 2);
+    assert!(matches!(
+        read_vox(&bytes),
+        Err(VolumeError::Truncated { .. })
+    ));
+
+    let mut bytes = b"VOX \x96\0\0\0".to_vec();
+    bytes.extend_from_slice(b"SIZE\xff\xff\xff\xff\0\0\0\0");
+    assert!(matches!(
+        read_vox(&bytes),
+        Err(VolumeError::Truncated { .. })
+    ));
+
+    let mut content = u32::MAX.to_le_bytes().to_vec();
+    content.extend_from_slice(&[0, 0, 0, 1]);
+    let bytes = vox(&[size(1, 1, 1), chunk(b"XYZI", &content, &[])]);
+    assert!(matches!(
+        read_vox(&bytes),
+        Err(VolumeError::Truncated { .. })
+    ));
+}
+
+#[test]
+fn volume_meshes_with_the_voxel_colors() {
+    let mut palette = vec![0u8; 1024];
+    palette[0..4].copy_from_slice(&[255, 0, 0, 255]);
+    let vox = read_vox(&vox(&[
+        size(2, 1, 1),
+        xyzi(&[[0, 0, 0, 1], [1, 0, 0, 1]]),
+        chunk(b"RGBA", &palette, &[]),
+    ]))
+    .unwrap();
+
+    // Blurring spreads the two voxels out, lowering their peak
+    for (blur, threshold) in [(0.0, 0.5), (0.7, 0.2)] {
+        let volume = VoxelVolume::new(&vox.models[0], &vox.palette, blur).unwrap();
+        let mesh = polygonize_with(&volume, &volume.grid(), threshold, &MeshOptions::default());
+
+        assert!(!mesh.indices.is_empty());
+        assert_eq!(mesh.colors.len() / 4, mesh.vertices.len() / 3);
+        for color in mesh.colors.chunks(4) {
+            for (channel, expected) in color.iter().zip([1.0, 0.0, 0.0, 1.0]) {
+                assert!((channel - expected).abs() < 1e-4);
+            }
+        }
+
+        // Both voxels lie within the surface
+        let max_x = mesh
+            .vertices
+            .chunks(3)
+            .map(|v| v[0])
+            .fold(f32::MIN, f32::max);
+        let min_x = mesh
+            .vertices
+            .chunks(3)
+            .map(|v| v[0])
+            .fold(f32::MAX, f32::min);
+        assert!(min_x < 0.0 && max_x > 1.0);
+    }
+}
+
+#[test]
+fn volume_blur_is_checked_and_capped() {
+    let vox = read_vox(&vox(&[size(3, 2, 1), xyzi(&[[1, 1, 0, 1]])])).unwrap();
+    let model = &vox.models[0];
+
+    for blur in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert!(matches!(
+            VoxelVolume::new(model, &vox.palette, blur),
+            Err(VolumeError::Format(_))
+        ));
+    }
+
+    // Past the model's longest side, wider blurs pad no further
+    let padded = |blur| {
+        let grid = VoxelVolume::new(model, &vox.palette, blur).unwrap().grid();
+        (grid.nx, grid.ny, grid.nz)
+    };
+    assert_eq!(padded(0.0), (4, 3, 2));
+    assert_eq!(padded(0.5), (8, 7, 6));
+    assert_eq!(padded(1.0), (10, 9, 8));
+    assert_eq!(padded(1.0e30), padded(1.0));
+
+    let volume = VoxelVolume::new(model, &vox.palette, 1.0e30).unwrap();
+    assert!(volume.value(1.0, 1.0, 0.0) > 0.0);
+}